@@ -13,16 +13,53 @@
  * Credits to Alexandru Bereghici: https://github.com/chrisllontop/keyv-rust
  */
 
+use libsql::TransactionBehavior;
 use libsql::{params, params_from_iter};
 use libsql::{Builder, Connection};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+use crate::store::{
+    decode_cursor, encode_cursor, increment_error, prefix_upper_bound,
+    ttl_millis,
+};
+use crate::{
+    EntryMeta, ScanOptions, ScanPage, Store, StoreError, StoreModel,
+    StoreTransaction, TransactionalStore, Ttl, DEFAULT_NAMESPACE_NAME,
+};
+
+/// SQL expression evaluating to the current UTC time in epoch milliseconds.
+///
+/// Expiry is always computed by the database so that every client writing to
+/// the same (possibly remote) database agrees on the current time.
+const NOW_MS: &str =
+    "CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)";
+
+/// Maximum length of a table name accepted by `KyvalStoreBuilder`.
+pub const MAX_TABLE_NAME_LEN: usize = 64;
+
+/// Maximum number of parameters bound to a single statement.
+///
+/// SQLite builds before 3.32 default `SQLITE_MAX_VARIABLE_NUMBER` to 999, so
+/// batched queries stay below it to work against any server.
+const MAX_QUERY_PARAMS: usize = 999;
 
-use crate::{Store, StoreError, StoreModel, DEFAULT_NAMESPACE_NAME};
+/// Version of the table schema written by this release.
+///
+/// Bump it whenever a step is added to `KyvalStore::migrate`.
+pub const SCHEMA_VERSION: u64 = 6;
+
+/// Table recording the schema version of every Kyval table in the database.
+const SCHEMA_TABLE: &str = "kyval_schema";
+
+/// Maximum number of expired rows deleted by a single sweep statement.
+pub const DEFAULT_SWEEP_BATCH_SIZE: usize = 1000;
 
 /// Builder for creating a `KyvalStore`.
 ///
@@ -73,11 +110,36 @@ use crate::{Store, StoreError, StoreModel, DEFAULT_NAMESPACE_NAME};
 ///         .unwrap();
 /// }
 /// ```
+///
+/// ## Sweeping Expired Entries in the Background
+///
+/// ```rust,no_run
+/// # use std::time::Duration;
+/// # use kyval::adapter::KyvalStoreBuilder;
+/// #[tokio::main]
+/// async fn main() {
+///     let store = KyvalStoreBuilder::new()
+///         .uri(":memory:")
+///         .sweep_interval(Duration::from_secs(60))
+///         .sweep_batch_size(500)
+///         .build()
+///         .await
+///         .unwrap();
+/// }
+/// ```
 pub struct KyvalStoreBuilder {
     uri: Option<PathBuf>,
     token: Option<String>,
     connnection: Option<Arc<Connection>>,
     table_name: Option<String>,
+    sweep_interval: Option<Duration>,
+    sweep_batch_size: usize,
+}
+
+impl Default for KyvalStoreBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl KyvalStoreBuilder {
@@ -87,13 +149,18 @@ impl KyvalStoreBuilder {
             token: None,
             connnection: None,
             table_name: None,
+            sweep_interval: None,
+            sweep_batch_size: DEFAULT_SWEEP_BATCH_SIZE,
         }
     }
 
     /// Sets the table name for the `KyvalStore`.
     ///
     /// This method configures the table name to be used by the store. If not set,
-    /// `DEFAULT_TABLE_NAME` from the configuration will be used.
+    /// `DEFAULT_NAMESPACE_NAME` will be used. The name must start with an ASCII
+    /// letter or underscore, contain only ASCII letters, digits and underscores,
+    /// be at most `MAX_TABLE_NAME_LEN` characters long and must not use SQLite's
+    /// reserved `sqlite_` prefix. Invalid names are rejected by `build`.
     pub fn table_name<S: Into<String>>(mut self, table: S) -> Self {
         self.table_name = Some(table.into());
         self
@@ -118,12 +185,34 @@ impl KyvalStoreBuilder {
     /// Uses an existing connection for the `KyvalStore`.
     ///
     /// This method allows for using an already configured `Pool`. If set,
-    /// the `uri` option is ignored.
+    /// the `uri` option is ignored. Stores sharing a connection take turns
+    /// using it, and wait for each other's transactions.
     pub fn connnection(mut self, connnection: Arc<Connection>) -> Self {
         self.connnection = Some(connnection);
         self
     }
 
+    /// Enables the background sweeper, which deletes expired entries every
+    /// `interval`.
+    ///
+    /// The sweeper is spawned on the current tokio runtime when the store is
+    /// initialized and stops once the store is dropped. Without it, expired
+    /// entries are hidden from reads but stay in the table until they are
+    /// overwritten or purged with `Store::purge_expired`.
+    pub fn sweep_interval(mut self, interval: Duration) -> Self {
+        self.sweep_interval = Some(interval);
+        self
+    }
+
+    /// Sets how many expired entries the sweeper deletes per statement.
+    ///
+    /// Smaller batches keep each delete short at the cost of more round-trips.
+    /// Defaults to `DEFAULT_SWEEP_BATCH_SIZE`.
+    pub fn sweep_batch_size(mut self, batch_size: usize) -> Self {
+        self.sweep_batch_size = batch_size.max(1);
+        self
+    }
+
     /// Builds the `KyvalStore` based on the provided configurations.
     ///
     /// Finalizes the builder and creates an `KyvalStore` instance.
@@ -131,18 +220,26 @@ impl KyvalStoreBuilder {
     ///
     /// # Returns
     /// This method returns a `Result` which, on success, contains the initialized `KyvalStore`.
-    /// On failure, it returns a `StoreError` indicating what went wrong during the initialization.
+    /// On failure, it returns a `StoreError` indicating what went wrong during the initialization,
+    /// such as `StoreError::Configuration` when neither a URI nor a connection is set, the
+    /// local database cannot be opened at the given path, or the remote URL is invalid.
     pub async fn build(self) -> Result<KyvalStore, StoreError> {
         let connnection = match self.connnection {
             Some(connnection) => connnection,
             None => {
-                let path = self
-                    .uri
-                    .expect("KyvalStore requires either a URI or an existing connnection to be set");
+                let path = self.uri.ok_or_else(|| {
+                    StoreError::Configuration(
+                        "KyvalStore requires either a URI or an existing connnection to be set"
+                            .to_string(),
+                    )
+                })?;
 
                 // If the token is set, use the remote database connection.
-                let db = if let Some(token) = self.token {
-                    Builder::new_remote(path.display().to_string(), token)
+                let conn = if let Some(token) = self.token {
+                    let url = path.display().to_string();
+                    validate_remote_url(&url)?;
+
+                    let db = Builder::new_remote(url, token)
                         .build()
                         .await
                         .map_err(|_| {
@@ -150,21 +247,29 @@ impl KyvalStoreBuilder {
                                 "Failed to create database connection"
                                     .to_string(),
                             )
-                        })?
-                } else {
-                    Builder::new_local(path).build().await.map_err(|_| {
+                        })?;
+
+                    db.connect().map_err(|_| {
                         StoreError::ConnectionError(
-                            "Failed to create database connection".to_string(),
+                            "Failed to create database connnection".to_string(),
                         )
                     })?
+                } else {
+                    // A local database only fails to open because of the
+                    // path, such as a missing directory.
+                    let open = async {
+                        Builder::new_local(&path).build().await?.connect()
+                    };
+
+                    open.await.map_err(|e| {
+                        StoreError::Configuration(format!(
+                            "Failed to open the database at {}: {:?}",
+                            path.display(),
+                            e
+                        ))
+                    })?
                 };
 
-                let conn = db.connect().map_err(|_| {
-                    StoreError::ConnectionError(
-                        "Failed to create database connnection".to_string(),
-                    )
-                })?;
-
                 Arc::new(conn)
             }
         };
@@ -173,10 +278,17 @@ impl KyvalStoreBuilder {
             log::warn!("Table name not set, using default table name");
             DEFAULT_NAMESPACE_NAME.to_string()
         });
+        validate_table_name(&table_name)?;
+
+        let lock = connection_lock(&connnection);
 
         Ok(KyvalStore {
             connnection,
             table_name,
+            sweep_interval: self.sweep_interval,
+            sweep_batch_size: self.sweep_batch_size,
+            sweeper: Mutex::new(None),
+            lock,
         })
     }
 }
@@ -184,280 +296,2117 @@ impl KyvalStoreBuilder {
 pub struct KyvalStore {
     pub(crate) connnection: Arc<Connection>,
     pub(crate) table_name: String,
+    sweep_interval: Option<Duration>,
+    sweep_batch_size: usize,
+    /// Dropping this sender stops the background sweeper.
+    sweeper: Mutex<Option<oneshot::Sender<()>>>,
+    /// Serializes use of the connection, so that statements issued by one
+    /// operation never run inside another operation's transaction. Shared by
+    /// every store on the same connection.
+    lock: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl KyvalStore {
+    /// Returns the quoted table name, ready to be interpolated into SQL.
     fn get_table_name(&self) -> String {
-        self.table_name.clone()
+        quote_identifier(&self.table_name)
     }
-}
 
-impl Store for KyvalStore {
-    fn initialize(
+    /// Returns the quoted name of a schema object (index, trigger) that
+    /// belongs to the table.
+    fn get_object_name(&self, suffix: &str) -> String {
+        quote_identifier(&format!("{}_{}", self.table_name, suffix))
+    }
+
+    /// Reads the schema version recorded for the table, creating the schema
+    /// version table if needed. Returns `None` if nothing has been recorded.
+    async fn schema_version(
         &self,
-    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
-        let query = format!(
-            r#"
-                CREATE TABLE IF NOT EXISTS {table_name} (
-                    key TEXT PRIMARY KEY,
-                    value TEXT NOT NULL,
-                    updated_at TEXT DEFAULT (datetime('now', 'localtime')),
-                    UNIQUE(key)
-                ) STRICT;
-                CREATE INDEX IF NOT EXISTS {table_name}_key_idx ON {table_name} (key);
-                CREATE TRIGGER IF NOT EXISTS {table_name}_update_trigger
-                AFTER UPDATE ON {table_name}
-                BEGIN
-                    UPDATE {table_name} SET updated_at = datetime('now', 'localtime') WHERE key = NEW.key;
-                END;
-            "#,
-            table_name = self.get_table_name()
+        conn: &Connection,
+    ) -> Result<Option<u64>, StoreError> {
+        let create = format!(
+            "CREATE TABLE IF NOT EXISTS {} (table_name TEXT PRIMARY KEY, version INTEGER NOT NULL, removed_version INTEGER NOT NULL DEFAULT 0) STRICT",
+            SCHEMA_TABLE
         );
+        conn.execute(&create, params![]).await.map_err(|e| {
+            StoreError::QueryError(format!(
+                "Failed to create the schema version table: {:?}",
+                e
+            ))
+        })?;
+        // Tables created by older releases lack the version floor.
+        ensure_column(
+            conn,
+            SCHEMA_TABLE,
+            "removed_version",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
 
-        let conn = &*self.connnection;
-
-        Box::pin(async move {
-            conn.execute_batch(&query).await.map_err(|e| {
+        let query = format!(
+            "SELECT version FROM {} WHERE table_name = ?1",
+            SCHEMA_TABLE
+        );
+        let mut rows = conn
+            .query(&query, params![self.table_name.clone()])
+            .await
+            .map_err(|e| {
                 StoreError::QueryError(format!(
-                    "Failed to initialize the database table: {}",
+                    "Failed to read the schema version: {:?}",
                     e
                 ))
             })?;
 
-            Ok(())
-        })
+        match rows.next().await.map_err(|e| {
+            StoreError::QueryError(format!("Failed to iterate rows: {:?}", e))
+        })? {
+            Some(row) => Ok(Some(row.get(0).map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to get the schema version: {:?}",
+                    e
+                ))
+            })?)),
+            None => Ok(None),
+        }
     }
 
-    fn get(
+    /// Records `SCHEMA_VERSION` as the table's schema version.
+    async fn record_schema_version(
         &self,
-        key: &str,
-    ) -> Pin<
-        Box<dyn Future<Output = Result<Option<Value>, StoreError>> + Send + '_>,
-    > {
+        conn: &Connection,
+    ) -> Result<(), StoreError> {
         let query = format!(
-            "SELECT value FROM {} WHERE key = ?1 LIMIT 1",
-            self.get_table_name()
+            "INSERT INTO {} (table_name, version) VALUES (?1, ?2) ON CONFLICT(table_name) DO UPDATE SET version = EXCLUDED.version",
+            SCHEMA_TABLE
         );
+        conn.execute(
+            &query,
+            params![self.table_name.clone(), SCHEMA_VERSION as i64],
+        )
+        .await
+        .map_err(|e| {
+            StoreError::QueryError(format!(
+                "Failed to record the schema version: {:?}",
+                e
+            ))
+        })?;
+
+        Ok(())
+    }
 
-        let conn = &*self.connnection;
-        let key = key.to_string();
-
-        Box::pin(async move {
-            let start = Instant::now();
-
-            let mut stmt = conn.prepare(&query).await.map_err(|e| {
+    /// Checks whether the table has already been created.
+    async fn table_exists(
+        &self,
+        conn: &Connection,
+    ) -> Result<bool, StoreError> {
+        let mut rows = conn
+            .query(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                params![self.table_name.clone()],
+            )
+            .await
+            .map_err(|e| {
                 StoreError::QueryError(format!(
-                    "Failed to set the statement: {:?}",
+                    "Failed to read the database schema: {:?}",
                     e
                 ))
             })?;
 
-            let result =
-                stmt.query_row(params![key.clone()]).await.map_err(|e| {
+        Ok(rows
+            .next()
+            .await
+            .map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to iterate rows: {:?}",
+                    e
+                ))
+            })?
+            .is_some())
+    }
+
+    /// Applies the migration step that brings the table to schema `version`.
+    ///
+    /// Steps only ever get appended: once released, a step must not change,
+    /// since databases that already applied it will never run it again.
+    async fn migrate(
+        &self,
+        conn: &Connection,
+        version: u64,
+    ) -> Result<(), StoreError> {
+        let table_name = self.get_table_name();
+
+        match version {
+            // Entries gained an expiry and the TTL they were written with.
+            1 => {
+                ensure_column(conn, &table_name, "expires_at", "INTEGER")
+                    .await?;
+                ensure_column(conn, &table_name, "ttl", "INTEGER").await?;
+            }
+            // Older releases stored strings as bare text. Quote whatever is
+            // not valid JSON so every row decodes to the value it was given;
+            // numbers, booleans, arrays and objects are already valid JSON.
+            // They also wrote `null` as the empty string, which is read back
+            // as `null` rather than `""`.
+            2 => {
+                let upgrade = format!(
+                    "UPDATE {} SET value = CASE WHEN value = '' THEN 'null' ELSE json_quote(value) END WHERE NOT json_valid(value)",
+                    table_name
+                );
+                conn.execute(&upgrade, params![]).await.map_err(|e| {
                     StoreError::QueryError(format!(
-                        "Failed to fetch the value: {:?}",
+                        "Failed to upgrade legacy values: {:?}",
                         e
                     ))
                 })?;
+            }
+            // Entries gained a version for compare-and-swap.
+            3 => {
+                ensure_column(
+                    conn,
+                    &table_name,
+                    "version",
+                    "INTEGER NOT NULL DEFAULT 1",
+                )
+                .await?;
+            }
+            // Entries gained a creation time.
+            4 => {
+                ensure_column(conn, &table_name, "created_at", "INTEGER")
+                    .await?;
+            }
+            // Older releases kept `updated_at` as local time text, which
+            // cannot be compared across hosts in different time zones.
+            5 => {
+                ensure_column(conn, &table_name, "updated_at", "INTEGER")
+                    .await?;
+
+                let updated_at =
+                    column_type(conn, &table_name, "updated_at").await?;
+                if updated_at
+                    .is_some_and(|t| !t.eq_ignore_ascii_case("INTEGER"))
+                {
+                    self.rebuild_with_epoch_timestamps(conn).await?;
+                }
+            }
+            // Deleted entries raise the table's version floor, so that a
+            // recreated key never reuses a version. The delete trigger that
+            // maintains it is created by `initialize`, and the floor starts
+            // at 0 since earlier deletions were not tracked.
+            6 => {}
+            _ => {
+                return Err(StoreError::QueryError(format!(
+                    "No migration to schema version {}",
+                    version
+                )))
+            }
+        }
 
-            let row_value: String = result.get(0).map_err(|e| {
-                StoreError::QueryError(format!(
-                    "Failed to get the value: {:?}",
-                    e
-                ))
-            })?;
-
-            let value = serde_json::to_value(row_value)
-                .map_err(|e| StoreError::SerializationError { source: e })?;
+        log::debug!(
+            "Kyval store migrated {} to schema version {}",
+            self.table_name,
+            version
+        );
 
-            let duration = start.elapsed();
-            log::debug!(
-                "Kyval store get: {:?} | {} | {:?}",
-                duration,
-                key,
-                value
-            );
+        Ok(())
+    }
 
-            Ok(Some(value))
-        })
+    /// Builds the `CREATE TABLE` statement for a table named `table_name`,
+    /// which must already be quoted. Timestamps are UTC epoch milliseconds.
+    fn create_table_query(&self, table_name: &str) -> String {
+        format!(
+            r#"
+                CREATE TABLE IF NOT EXISTS {table_name} (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL,
+                    expires_at INTEGER,
+                    ttl INTEGER,
+                    version INTEGER NOT NULL DEFAULT 1,
+                    created_at INTEGER,
+                    updated_at INTEGER DEFAULT ({now}),
+                    UNIQUE(key)
+                ) STRICT
+            "#,
+            table_name = table_name,
+            now = NOW_MS
+        )
     }
 
-    fn list(
+    /// Rebuilds the table with `updated_at` as UTC epoch milliseconds,
+    /// converting the local time text written by older releases. Legacy
+    /// values are quoted as JSON on the way.
+    ///
+    /// A `STRICT` table cannot change a column's type in place, so the rows
+    /// are copied into a fresh table that then replaces the old one. Its
+    /// indexes and trigger are dropped along with it and have to be
+    /// recreated.
+    async fn rebuild_with_epoch_timestamps(
         &self,
-    ) -> Pin<
-        Box<
-            dyn Future<Output = Result<Vec<StoreModel>, StoreError>>
-                + Send
-                + '_,
-        >,
-    > {
+        conn: &Connection,
+    ) -> Result<(), StoreError> {
+        // The ':' keeps the scratch name out of reach of
+        // `validate_table_name`, so it cannot belong to another store.
+        let rebuilt = quote_identifier(&format!("{}:rebuild", self.table_name));
         let query = format!(
-            "SELECT key, value FROM {} ORDER BY key ASC;",
-            self.get_table_name()
+            r#"
+                {create_table};
+                INSERT INTO {rebuilt} (key, value, expires_at, ttl, version, created_at, updated_at)
+                SELECT key,
+                    CASE WHEN json_valid(value) THEN value WHEN value = '' THEN 'null' ELSE json_quote(value) END,
+                    expires_at, ttl, version, created_at,
+                    CAST((julianday(updated_at, 'utc') - 2440587.5) * 86400000 AS INTEGER)
+                FROM {table_name};
+                DROP TABLE {table_name};
+                ALTER TABLE {rebuilt} RENAME TO {table_name};
+            "#,
+            rebuilt = rebuilt,
+            create_table = self.create_table_query(&rebuilt),
+            table_name = self.get_table_name()
         );
 
-        let conn = &*self.connnection;
+        conn.execute_batch(&query).await.map_err(|e| {
+            StoreError::QueryError(format!(
+                "Failed to convert the timestamps: {:?}",
+                e
+            ))
+        })?;
 
-        Box::pin(async move {
-            let start = Instant::now();
+        Ok(())
+    }
 
-            let mut stmt = conn.prepare(&query).await.map_err(|e| {
-                StoreError::QueryError(format!(
-                    "Failed to set the statement: {:?}",
-                    e
-                ))
-            })?;
+    /// Builds the query reading the unexpired value of the key bound to `?1`.
+    fn get_query(&self) -> String {
+        format!(
+            "SELECT value FROM {table_name} WHERE key = ?1 AND (expires_at IS NULL OR expires_at > {now}) LIMIT 1",
+            table_name = self.get_table_name(),
+            now = NOW_MS
+        )
+    }
 
-            let mut results = stmt.query(params![]).await.map_err(|e| {
-                StoreError::QueryError(format!(
-                    "Failed to fetch the value: {:?}",
-                    e
-                ))
-            })?;
+    /// Builds the expression giving the version of a newly inserted entry,
+    /// one past the highest version ever deleted from the table.
+    fn first_version(&self) -> String {
+        format!(
+            "(SELECT COALESCE(MAX(removed_version), 0) + 1 FROM {} WHERE table_name = '{}')",
+            SCHEMA_TABLE, self.table_name
+        )
+    }
 
-            let mut items: Vec<StoreModel> = Vec::new();
+    /// Builds the query deleting the key bound to `?1`.
+    fn remove_query(&self) -> String {
+        format!("DELETE FROM {} WHERE key = ?1", self.get_table_name())
+    }
 
-            while let Some(row) = results.next().await.map_err(|e| {
+    /// Builds the upsert used by the `set` family, binding the key to `?1`,
+    /// the JSON encoded value to `?2` and the TTL in milliseconds to `?3`.
+    /// `returning` is appended to the statement, and may start with a `WHERE`
+    /// clause restricting when an existing row is overwritten.
+    fn upsert_query(&self, returning: &str) -> String {
+        format!(
+            "INSERT INTO {table_name} (key, value, expires_at, ttl, version, created_at) VALUES (?1, ?2, {now} + ?3, ?3, {version}, {now}) ON CONFLICT(key) DO UPDATE SET value = EXCLUDED.value, expires_at = EXCLUDED.expires_at, ttl = EXCLUDED.ttl, version = version + 1, created_at = CASE WHEN expires_at IS NOT NULL AND expires_at <= {now} THEN EXCLUDED.created_at ELSE created_at END{returning}",
+            table_name = self.get_table_name(),
+            now = NOW_MS,
+            version = self.first_version(),
+            returning = returning
+        )
+    }
+
+    /// Builds an atomic increment, binding the key to `?1` and the delta to
+    /// `?2`. A missing or expired key is (re)created holding the delta, with
+    /// no TTL. A live key is updated to `sum`, keeping its TTL, but only if
+    /// `guard` holds; otherwise the statement returns no row.
+    fn increment_query(&self, sum: &str, guard: &str) -> String {
+        format!(
+            "INSERT INTO {table_name} (key, value, version, created_at) VALUES (?1, CAST(?2 AS TEXT), {version}, {now}) ON CONFLICT(key) DO UPDATE SET value = CASE WHEN {expired} THEN EXCLUDED.value ELSE CAST({sum} AS TEXT) END, expires_at = CASE WHEN {expired} THEN NULL ELSE expires_at END, ttl = CASE WHEN {expired} THEN NULL ELSE ttl END, created_at = CASE WHEN {expired} THEN EXCLUDED.created_at ELSE created_at END, version = version + 1 WHERE {expired} OR ({guard}) RETURNING value",
+            table_name = self.get_table_name(),
+            now = NOW_MS,
+            expired = format!("(expires_at IS NOT NULL AND expires_at <= {})", NOW_MS),
+            version = self.first_version(),
+            sum = sum,
+            guard = guard
+        )
+    }
+
+    /// Runs a query built by `increment_query` and returns the new value, or
+    /// `None` if the guard rejected the increment.
+    async fn increment(
+        &self,
+        conn: &Connection,
+        query: &str,
+        key: &str,
+        delta: libsql::Value,
+    ) -> Result<Option<Value>, StoreError> {
+        let mut rows =
+            conn.query(query, params![key, delta]).await.map_err(|e| {
                 StoreError::QueryError(format!(
-                    "Failed to iterate rows: {:?}",
+                    "Failed to increment the value: {:?}",
                     e
                 ))
-            })? {
-                let key: String = row.get(0).map_err(|e| {
+            })?;
+
+        match rows.next().await.map_err(|e| {
+            StoreError::QueryError(format!("Failed to iterate rows: {:?}", e))
+        })? {
+            Some(row) => {
+                let row_value: String = row.get(0).map_err(|e| {
                     StoreError::QueryError(format!(
                         "Failed to get the value: {:?}",
                         e
                     ))
                 })?;
-                let row_value: String = row.get(1).map_err(|e| {
+
+                Ok(Some(decode_value(row_value)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the unexpired value of `key` along with its version.
+    async fn read_versioned(
+        &self,
+        conn: &Connection,
+        key: &str,
+    ) -> Result<Option<(Value, u64)>, StoreError> {
+        let query = format!(
+            "SELECT value, version FROM {table_name} WHERE key = ?1 AND (expires_at IS NULL OR expires_at > {now}) LIMIT 1",
+            table_name = self.get_table_name(),
+            now = NOW_MS
+        );
+
+        let mut rows = conn.query(&query, params![key]).await.map_err(|e| {
+            StoreError::QueryError(format!(
+                "Failed to fetch the value: {:?}",
+                e
+            ))
+        })?;
+
+        match rows.next().await.map_err(|e| {
+            StoreError::QueryError(format!("Failed to iterate rows: {:?}", e))
+        })? {
+            Some(row) => {
+                let row_value: String = row.get(0).map_err(|e| {
                     StoreError::QueryError(format!(
                         "Failed to get the value: {:?}",
                         e
                     ))
                 })?;
-                let value = serde_json::to_value(row_value).map_err(|e| {
-                    StoreError::SerializationError { source: e }
+                let version: u64 = row.get(1).map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to get the version: {:?}",
+                        e
+                    ))
                 })?;
 
-                items.push(StoreModel { key, value });
+                Ok(Some((decode_value(row_value), version)))
             }
-
-            let duration = start.elapsed();
-            log::debug!("Kyval store list: {:?} | {:?}", duration, items);
-
-            Ok(items)
-        })
+            None => Ok(None),
+        }
     }
 
-    fn set(
+    /// Runs a conditional write binding the key to `?1`, the JSON encoded value
+    /// to `?2` and the TTL in milliseconds to `?3`, and reports whether the
+    /// statement returned a row, i.e. whether the write happened.
+    fn write_if(
         &self,
+        operation: &'static str,
+        query: String,
         key: &str,
         value: Value,
-        _ttl: Option<u64>,
-    ) -> Pin<
-        Box<
-            dyn Future<Output = Result<Option<StoreModel>, StoreError>>
-                + Send
-                + '_,
-        >,
-    > {
-        let query = format!(
-            "INSERT INTO {} (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = EXCLUDED.value",
-            self.get_table_name()
-        );
-
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
         let conn = &*self.connnection;
         let key = key.to_string();
+        let ttl = ttl.map(ttl_millis);
 
         Box::pin(async move {
-            let start = Instant::now();
-
-            let value_str = match value {
-                Value::String(ref s) => s.clone(), // If the value is a string, use the original string.
-                Value::Number(ref n) => n.to_string(), // If the value is a number, use the number string representation.
-                Value::Null => "".to_string(), // If value is null, use the empty string.
-                _ => value.to_string(), // If the value is an object or other type, serialize it as JSON.
-            };
+            let _lock = self.lock.lock().await;
 
-            let mut stmt = conn.prepare(&query).await.map_err(|_| {
-                StoreError::QueryError(
-                    "Failed to set the statement".to_string(),
-                )
-            })?;
+            let start = Instant::now();
 
-            let mut response = stmt
-                .query(params![key.clone(), value_str.clone()])
+            let mut rows = conn
+                .query(&query, params![key.clone(), value.to_string(), ttl])
                 .await
-                .map_err(|_| {
-                    StoreError::QueryError(
-                        "Failed to set the value".to_string(),
-                    )
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to set the value: {:?}",
+                        e
+                    ))
                 })?;
 
-            let result = match response.next().await.map_err(|e| {
-                StoreError::QueryError(format!(
-                    "Failed to iterate rows: {:?}",
-                    e
-                ))
-            })? {
-                Some(row) => {
-                    let row_key = row.get_str(0).map_err(|e| {
-                        StoreError::QueryError(format!(
-                            "Failed to get the key: {:?}",
-                            e
-                        ))
-                    })?;
-
-                    let row_value = row.get_value(1).map_err(|e| {
-                        StoreError::QueryError(format!(
-                            "Failed to get the value: {:?}",
-                            e
-                        ))
-                    })?;
-
-                    Some(StoreModel {
-                        key: row_key.to_string(),
-                        value: serde_json::to_value(row_value).map_err(
-                            |e| StoreError::SerializationError { source: e },
-                        )?,
-                    })
-                }
-                None => None,
-            };
+            let written = rows
+                .next()
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to iterate rows: {:?}",
+                        e
+                    ))
+                })?
+                .is_some();
 
             let duration = start.elapsed();
             log::debug!(
-                "Kyval store set: {:?} | {} | {}",
+                "Kyval store {}: {:?} | {} | {}",
+                operation,
                 duration,
                 key,
-                value_str
+                written
             );
 
-            Ok(result)
+            Ok(written)
         })
     }
 
-    fn remove(
+    /// Runs an expiry update for a single unexpired key and reports whether
+    /// the key was found. `?1` is bound to the key and `?2` to `ttl`, if any.
+    fn update_expiry(
         &self,
+        operation: &'static str,
+        query: String,
         key: &str,
-    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
-        let query =
-            format!("DELETE FROM {} WHERE key = ?1", self.get_table_name());
-
+        ttl: Option<i64>,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
         let conn = &*self.connnection;
-
         let key = key.to_string();
 
         Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
             let start = Instant::now();
 
-            let mut stmt = conn.prepare(&query).await.map_err(|_| {
-                StoreError::QueryError(
-                    "Failed to set the statement".to_string(),
-                )
-            })?;
+            let mut args = vec![libsql::Value::from(key.clone())];
+            args.extend(ttl.map(libsql::Value::from));
 
-            stmt.execute(params![key.clone()]).await.map_err(|_| {
+            let updated = conn
+                .execute(&query, params_from_iter(args))
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to update the expiry: {:?}",
+                        e
+                    ))
+                })?;
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store {}: {:?} | {} | {}",
+                operation,
+                duration,
+                key,
+                updated
+            );
+
+            Ok(updated > 0)
+        })
+    }
+
+    /// Spawns the background sweeper if one is configured and not running yet.
+    fn spawn_sweeper(&self) {
+        let Some(interval) = self.sweep_interval else {
+            return;
+        };
+
+        let mut sweeper = self.sweeper.lock().unwrap();
+        if sweeper.is_some() {
+            return;
+        }
+
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
+        let conn = self.connnection.clone();
+        let lock = self.lock.clone();
+        let table_name = self.get_table_name();
+        let batch_size = self.sweep_batch_size;
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(
+                tokio::time::MissedTickBehavior::Delay,
+            );
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => break,
+                    _ = ticker.tick() => {
+                        let result = purge_expired_rows(
+                            &conn,
+                            &lock,
+                            &table_name,
+                            batch_size,
+                        )
+                        .await;
+                        match result {
+                            Ok(removed) => log::debug!(
+                                "Kyval store sweeper removed {} expired keys",
+                                removed
+                            ),
+                            Err(e) => log::warn!(
+                                "Kyval store sweeper failed: {}",
+                                e
+                            ),
+                        }
+                    }
+                }
+            }
+
+            log::debug!("Kyval store sweeper stopped");
+        });
+
+        *sweeper = Some(shutdown_tx);
+    }
+}
+
+/// Checks that `name` is a plain SQL identifier that is safe to use as a
+/// table name, and as the prefix of the table's index and trigger names.
+/// Names reserved by SQLite and the schema version table are rejected.
+fn validate_table_name(name: &str) -> Result<(), StoreError> {
+    let mut chars = name.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    let valid_rest = chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let reserved = name.to_ascii_lowercase().starts_with("sqlite_")
+        || name.eq_ignore_ascii_case(SCHEMA_TABLE);
+
+    if !valid_start
+        || !valid_rest
+        || reserved
+        || name.len() > MAX_TABLE_NAME_LEN
+    {
+        return Err(StoreError::InvalidTableName(name.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Statement locks of the connections in use. Each entry keeps its
+/// connection's allocation alive, so that a new connection cannot reuse the
+/// address of one whose lock is still held.
+#[allow(clippy::type_complexity)]
+static CONNECTION_LOCKS: Mutex<
+    Vec<(Weak<Connection>, Weak<tokio::sync::Mutex<()>>)>,
+> = Mutex::new(Vec::new());
+
+/// Returns the statement lock of `conn`, shared by every store built on it.
+fn connection_lock(conn: &Arc<Connection>) -> Arc<tokio::sync::Mutex<()>> {
+    let mut locks = CONNECTION_LOCKS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    locks.retain(|(_, lock)| lock.strong_count() > 0);
+
+    let existing = locks
+        .iter()
+        .find(|(connection, _)| std::ptr::eq(connection.as_ptr(), &**conn))
+        .and_then(|(_, lock)| lock.upgrade());
+
+    existing.unwrap_or_else(|| {
+        let lock = Arc::new(tokio::sync::Mutex::new(()));
+        locks.push((Arc::downgrade(conn), Arc::downgrade(&lock)));
+        lock
+    })
+}
+
+/// Checks that a remote database URL uses a scheme libSQL can connect with.
+/// The remote builder only reports a bad URL on the first query.
+fn validate_remote_url(url: &str) -> Result<(), StoreError> {
+    const SCHEMES: [&str; 5] =
+        ["libsql://", "https://", "http://", "wss://", "ws://"];
+
+    let valid = SCHEMES.iter().any(|scheme| {
+        url.get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+            && url.len() > scheme.len()
+    });
+
+    if !valid {
+        return Err(StoreError::Configuration(format!(
+            "Invalid remote database URL: {}",
+            url
+        )));
+    }
+
+    Ok(())
+}
+
+/// Quotes an SQL identifier, escaping any embedded double quotes.
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Builds a `?1, ?2, ...` placeholder list for `count` parameters.
+fn placeholders(count: usize) -> String {
+    (1..=count)
+        .map(|i| format!("?{}", i))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Builds a `WHERE` condition matching unexpired keys that start with
+/// `prefix`, along with its parameters.
+///
+/// The prefix is matched as a key range rather than with `LIKE`, so it can
+/// use the primary key index and needs no escaping.
+fn prefix_filter(prefix: &str) -> (String, Vec<libsql::Value>) {
+    let mut filter = format!("(expires_at IS NULL OR expires_at > {})", NOW_MS);
+    let mut values = Vec::new();
+
+    if !prefix.is_empty() {
+        values.push(libsql::Value::Text(prefix.to_string()));
+        filter.push_str(" AND key >= ?1");
+
+        if let Some(upper) = prefix_upper_bound(prefix) {
+            values.push(libsql::Value::Text(upper));
+            filter.push_str(" AND key < ?2");
+        }
+    }
+
+    (filter, values)
+}
+
+/// Decodes a stored value, which is kept as JSON text.
+///
+/// Older releases stored strings and numbers as bare text, so anything
+/// that is not valid JSON is returned as a string rather than rejected.
+fn decode_value(raw: String) -> Value {
+    serde_json::from_str(&raw).unwrap_or(Value::String(raw))
+}
+
+/// Reads a `key, value, expires_at` row into a `StoreModel`.
+fn read_model(row: &libsql::Row) -> Result<StoreModel, StoreError> {
+    let key: String = row.get(0).map_err(|e| {
+        StoreError::QueryError(format!("Failed to get the key: {:?}", e))
+    })?;
+    let value: String = row.get(1).map_err(|e| {
+        StoreError::QueryError(format!("Failed to get the value: {:?}", e))
+    })?;
+    let expires_at: Option<u64> = row.get(2).map_err(|e| {
+        StoreError::QueryError(format!("Failed to get the expiry: {:?}", e))
+    })?;
+
+    Ok(StoreModel {
+        key,
+        value: decode_value(value),
+        expires_at,
+    })
+}
+
+/// Reads a `key, created_at, updated_at, expires_at, size, version` row into
+/// an `EntryMeta`.
+fn read_meta(row: &libsql::Row) -> Result<EntryMeta, StoreError> {
+    let column = |index: i32, name: &str| -> Result<Option<u64>, StoreError> {
+        row.get(index).map_err(|e| {
+            StoreError::QueryError(format!(
+                "Failed to get the {}: {:?}",
+                name, e
+            ))
+        })
+    };
+
+    Ok(EntryMeta {
+        key: row.get(0).map_err(|e| {
+            StoreError::QueryError(format!("Failed to get the key: {:?}", e))
+        })?,
+        created_at: column(1, "creation time")?,
+        updated_at: column(2, "update time")?,
+        expires_at: column(3, "expiry")?,
+        size: column(4, "size")?.unwrap_or(0),
+        version: column(5, "version")?.unwrap_or(1),
+    })
+}
+
+/// Deletes every expired row of `table_name`, `batch_size` rows at a time,
+/// and returns how many rows were deleted.
+async fn purge_expired_rows(
+    conn: &Connection,
+    lock: &tokio::sync::Mutex<()>,
+    table_name: &str,
+    batch_size: usize,
+) -> Result<u64, StoreError> {
+    let query = format!(
+        "DELETE FROM {table_name} WHERE key IN (SELECT key FROM {table_name} WHERE expires_at <= {now} LIMIT ?1)",
+        table_name = table_name,
+        now = NOW_MS
+    );
+
+    let mut total = 0;
+    loop {
+        // Only hold the lock per batch, so large purges don't stall readers.
+        let guard = lock.lock().await;
+        let removed = conn
+            .execute(&query, params![batch_size as i64])
+            .await
+            .map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to purge expired keys: {:?}",
+                    e
+                ))
+            })?;
+
+        drop(guard);
+
+        total += removed;
+        if removed < batch_size as u64 {
+            break;
+        }
+
+        // Give other tasks a chance to use the connection between batches.
+        tokio::task::yield_now().await;
+    }
+
+    Ok(total)
+}
+
+/// Returns the declared type of `column` in `table_name`, or `None` if the
+/// table has no such column.
+async fn column_type(
+    conn: &Connection,
+    table_name: &str,
+    column: &str,
+) -> Result<Option<String>, StoreError> {
+    let mut rows = conn
+        .query(&format!("PRAGMA table_info({})", table_name), params![])
+        .await
+        .map_err(|e| {
+            StoreError::QueryError(format!(
+                "Failed to read the table schema: {:?}",
+                e
+            ))
+        })?;
+
+    while let Some(row) = rows.next().await.map_err(|e| {
+        StoreError::QueryError(format!("Failed to iterate rows: {:?}", e))
+    })? {
+        let name: String = row.get(1).map_err(|e| {
+            StoreError::QueryError(format!(
+                "Failed to get the column name: {:?}",
+                e
+            ))
+        })?;
+        if name == column {
+            let declared: String = row.get(2).map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to get the column type: {:?}",
+                    e
+                ))
+            })?;
+            return Ok(Some(declared));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Adds `column` to `table_name` when it is missing.
+///
+/// `CREATE TABLE IF NOT EXISTS` leaves tables created by older releases
+/// untouched, so new columns have to be added to them explicitly.
+async fn ensure_column(
+    conn: &Connection,
+    table_name: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), StoreError> {
+    if column_type(conn, table_name, column).await?.is_some() {
+        return Ok(());
+    }
+
+    let query = format!(
+        "ALTER TABLE {} ADD COLUMN {} {}",
+        table_name, column, definition
+    );
+    conn.execute(&query, params![]).await.map_err(|e| {
+        StoreError::QueryError(format!(
+            "Failed to add the {} column: {:?}",
+            column, e
+        ))
+    })?;
+
+    Ok(())
+}
+
+impl Store for KyvalStore {
+    fn as_transactional(&self) -> Option<&dyn TransactionalStore> {
+        Some(self)
+    }
+
+    fn initialize(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        let create_table = self.create_table_query(&self.get_table_name());
+        let query = format!(
+            r#"
+                CREATE INDEX IF NOT EXISTS {key_index} ON {table_name} (key);
+                CREATE INDEX IF NOT EXISTS {expires_at_index} ON {table_name} (expires_at);
+                CREATE TRIGGER IF NOT EXISTS {update_trigger}
+                AFTER UPDATE ON {table_name}
+                BEGIN
+                    UPDATE {table_name} SET updated_at = {now} WHERE key = NEW.key;
+                END;
+                CREATE TRIGGER IF NOT EXISTS {delete_trigger}
+                AFTER DELETE ON {table_name}
+                BEGIN
+                    UPDATE {schema_table} SET removed_version = MAX(removed_version, OLD.version) WHERE table_name = '{name}';
+                END;
+            "#,
+            table_name = self.get_table_name(),
+            key_index = self.get_object_name("key_idx"),
+            expires_at_index = self.get_object_name("expires_at_idx"),
+            update_trigger = self.get_object_name("update_trigger"),
+            delete_trigger = self.get_object_name("delete_trigger"),
+            schema_table = SCHEMA_TABLE,
+            name = self.table_name,
+            now = NOW_MS
+        );
+        let drop_trigger = format!(
+            "DROP TRIGGER IF EXISTS {}",
+            self.get_object_name("update_trigger")
+        );
+
+        let conn = &*self.connnection;
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to begin the transaction: {:?}",
+                        e
+                    ))
+                })?;
+
+            let recorded = self.schema_version(&tx).await?;
+            if let Some(found) = recorded.filter(|v| *v > SCHEMA_VERSION) {
+                return Err(StoreError::UnsupportedSchemaVersion {
+                    table: self.table_name.clone(),
+                    found,
+                    supported: SCHEMA_VERSION,
+                });
+            }
+
+            // Tables without a record predate schema versioning, and may have
+            // been created by any older release. Every step is written to be
+            // a no-op where its change is already in place.
+            let current = match recorded {
+                Some(version) => version,
+                None if self.table_exists(&tx).await? => 0,
+                None => SCHEMA_VERSION,
+            };
+
+            tx.execute(&create_table, params![]).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to initialize the database table: {}",
+                    e
+                ))
+            })?;
+
+            if current < SCHEMA_VERSION {
+                // The trigger is recreated below, so migrating rows does not
+                // count as updating them and its body matches the new schema.
+                tx.execute(&drop_trigger, params![]).await.map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to drop the update trigger: {:?}",
+                        e
+                    ))
+                })?;
+
+                for version in current + 1..=SCHEMA_VERSION {
+                    self.migrate(&tx, version).await?;
+                }
+            }
+
+            tx.execute_batch(&query).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to initialize the database table: {}",
+                    e
+                ))
+            })?;
+
+            if recorded != Some(SCHEMA_VERSION) {
+                self.record_schema_version(&tx).await?;
+            }
+
+            tx.commit().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to commit the transaction: {:?}",
+                    e
+                ))
+            })?;
+
+            self.spawn_sweeper();
+
+            Ok(())
+        })
+    }
+
+    fn get(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Option<Value>, StoreError>> + Send + '_>,
+    > {
+        let query = self.get_query();
+
+        let conn = &*self.connnection;
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut stmt = conn.prepare(&query).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to set the statement: {:?}",
+                    e
+                ))
+            })?;
+
+            let mut rows =
+                stmt.query(params![key.clone()]).await.map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to fetch the value: {:?}",
+                        e
+                    ))
+                })?;
+
+            // A missing or expired key yields no row at all.
+            let value = match rows.next().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to iterate rows: {:?}",
+                    e
+                ))
+            })? {
+                Some(row) => {
+                    let row_value: String = row.get(0).map_err(|e| {
+                        StoreError::QueryError(format!(
+                            "Failed to get the value: {:?}",
+                            e
+                        ))
+                    })?;
+
+                    Some(decode_value(row_value))
+                }
+                None => None,
+            };
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store get: {:?} | {} | {:?}",
+                duration,
+                key,
+                value
+            );
+
+            Ok(value)
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn get_many(
+        &self,
+        keys: &[&str],
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<HashMap<String, Value>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let conn = &*self.connnection;
+        let table_name = self.get_table_name();
+        let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<String>>();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut values = HashMap::with_capacity(keys.len());
+
+            // One query per chunk keeps every statement below the server's
+            // parameter limit.
+            for chunk in keys.chunks(MAX_QUERY_PARAMS) {
+                let query = format!(
+                    "SELECT key, value FROM {table_name} WHERE key IN ({placeholders}) AND (expires_at IS NULL OR expires_at > {now})",
+                    table_name = table_name,
+                    placeholders = placeholders(chunk.len()),
+                    now = NOW_MS
+                );
+
+                let mut stmt = conn.prepare(&query).await.map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to set the statement: {:?}",
+                        e
+                    ))
+                })?;
+
+                let mut rows = stmt
+                    .query(params_from_iter(chunk.to_vec()))
+                    .await
+                    .map_err(|e| {
+                        StoreError::QueryError(format!(
+                            "Failed to fetch the values: {:?}",
+                            e
+                        ))
+                    })?;
+
+                while let Some(row) = rows.next().await.map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to iterate rows: {:?}",
+                        e
+                    ))
+                })? {
+                    let key: String = row.get(0).map_err(|e| {
+                        StoreError::QueryError(format!(
+                            "Failed to get the key: {:?}",
+                            e
+                        ))
+                    })?;
+                    let row_value: String = row.get(1).map_err(|e| {
+                        StoreError::QueryError(format!(
+                            "Failed to get the value: {:?}",
+                            e
+                        ))
+                    })?;
+
+                    values.insert(key, decode_value(row_value));
+                }
+            }
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store get_many: {:?} | {} of {} keys",
+                duration,
+                values.len(),
+                keys.len()
+            );
+
+            Ok(values)
+        })
+    }
+
+    fn exists(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let query = format!(
+            "SELECT 1 FROM {} WHERE key = ?1 AND (expires_at IS NULL OR expires_at > {}) LIMIT 1",
+            self.get_table_name(),
+            NOW_MS
+        );
+
+        let conn = &*self.connnection;
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut stmt = conn.prepare(&query).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to set the statement: {:?}",
+                    e
+                ))
+            })?;
+
+            let mut rows =
+                stmt.query(params![key.clone()]).await.map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to check the key: {:?}",
+                        e
+                    ))
+                })?;
+
+            let exists = rows
+                .next()
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to iterate rows: {:?}",
+                        e
+                    ))
+                })?
+                .is_some();
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store exists: {:?} | {} | {}",
+                duration,
+                key,
+                exists
+            );
+
+            Ok(exists)
+        })
+    }
+
+    fn metadata(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<EntryMeta>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let query = format!(
+            "SELECT key, created_at, updated_at, expires_at, length(CAST(value AS BLOB)), version FROM {} WHERE key = ?1 AND (expires_at IS NULL OR expires_at > {}) LIMIT 1",
+            self.get_table_name(),
+            NOW_MS
+        );
+
+        let conn = &*self.connnection;
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut stmt = conn.prepare(&query).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to set the statement: {:?}",
+                    e
+                ))
+            })?;
+
+            let mut rows =
+                stmt.query(params![key.clone()]).await.map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to fetch the metadata: {:?}",
+                        e
+                    ))
+                })?;
+
+            let meta = match rows.next().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to iterate rows: {:?}",
+                    e
+                ))
+            })? {
+                Some(row) => Some(read_meta(&row)?),
+                None => None,
+            };
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store metadata: {:?} | {} | {:?}",
+                duration,
+                key,
+                meta
+            );
+
+            Ok(meta)
+        })
+    }
+
+    fn list(
+        &self,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Vec<StoreModel>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let query = format!(
+            "SELECT key, value, expires_at FROM {} WHERE expires_at IS NULL OR expires_at > {} ORDER BY key ASC;",
+            self.get_table_name(),
+            NOW_MS
+        );
+
+        let conn = &*self.connnection;
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut stmt = conn.prepare(&query).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to set the statement: {:?}",
+                    e
+                ))
+            })?;
+
+            let mut results = stmt.query(params![]).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to fetch the value: {:?}",
+                    e
+                ))
+            })?;
+
+            let mut items: Vec<StoreModel> = Vec::new();
+
+            while let Some(row) = results.next().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to iterate rows: {:?}",
+                    e
+                ))
+            })? {
+                items.push(read_model(&row)?);
+            }
+
+            let duration = start.elapsed();
+            log::debug!("Kyval store list: {:?} | {:?}", duration, items);
+
+            Ok(items)
+        })
+    }
+
+    fn scan(
+        &self,
+        options: ScanOptions,
+    ) -> Pin<Box<dyn Future<Output = Result<ScanPage, StoreError>> + Send + '_>>
+    {
+        let conn = &*self.connnection;
+        let table_name = self.get_table_name();
+
+        Box::pin(async move {
+            let mut conditions = vec![format!(
+                "(expires_at IS NULL OR expires_at > {})",
+                NOW_MS
+            )];
+            let mut values: Vec<libsql::Value> = Vec::new();
+
+            let mut bind = |condition: &str, value: String| {
+                values.push(libsql::Value::Text(value));
+                conditions.push(format!("key {} ?{}", condition, values.len()));
+            };
+
+            if let Some(prefix) = options.prefix {
+                if let Some(upper) = prefix_upper_bound(&prefix) {
+                    bind("<", upper);
+                }
+                bind(">=", prefix);
+            }
+            if let Some(start) = options.start {
+                bind(">=", start);
+            }
+            if let Some(end) = options.end {
+                bind("<", end);
+            }
+            if let Some(cursor) = options.cursor.as_deref() {
+                let after = decode_cursor(cursor)?;
+                bind(if options.reverse { "<" } else { ">" }, after);
+            }
+
+            let limit = options.limit.max(1);
+            let query = format!(
+                "SELECT key, value, expires_at FROM {} WHERE {} ORDER BY key {} LIMIT {};",
+                table_name,
+                conditions.join(" AND "),
+                if options.reverse { "DESC" } else { "ASC" },
+                limit + 1
+            );
+
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut stmt = conn.prepare(&query).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to set the statement: {:?}",
+                    e
+                ))
+            })?;
+
+            let mut results =
+                stmt.query(params_from_iter(values)).await.map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to scan the keys: {:?}",
+                        e
+                    ))
+                })?;
+
+            let mut items: Vec<StoreModel> = Vec::new();
+
+            while let Some(row) = results.next().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to iterate rows: {:?}",
+                    e
+                ))
+            })? {
+                items.push(read_model(&row)?);
+            }
+
+            // One row past the limit is fetched to tell whether another page
+            // exists without a separate count.
+            let cursor = if items.len() > limit {
+                items.truncate(limit);
+                items.last().map(|item| encode_cursor(&item.key))
+            } else {
+                None
+            };
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store scan: {:?} | {} items",
+                duration,
+                items.len()
+            );
+
+            Ok(ScanPage { items, cursor })
+        })
+    }
+
+    fn count(
+        &self,
+        prefix: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>
+    {
+        let (filter, values) = prefix_filter(prefix);
+        let query = format!(
+            "SELECT COUNT(*) FROM {} WHERE {}",
+            self.get_table_name(),
+            filter
+        );
+
+        let conn = &*self.connnection;
+        let prefix = prefix.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut stmt = conn.prepare(&query).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to set the statement: {:?}",
+                    e
+                ))
+            })?;
+
+            let row = stmt.query_row(params_from_iter(values)).await.map_err(
+                |e| {
+                    StoreError::QueryError(format!(
+                        "Failed to count the keys: {:?}",
+                        e
+                    ))
+                },
+            )?;
+
+            let count: u64 = row.get(0).map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to get the count: {:?}",
+                    e
+                ))
+            })?;
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store count: {:?} | {} | {}",
+                duration,
+                prefix,
+                count
+            );
+
+            Ok(count)
+        })
+    }
+
+    fn keys(
+        &self,
+        prefix: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Vec<String>, StoreError>> + Send + '_>,
+    > {
+        let (filter, values) = prefix_filter(prefix);
+        let query = format!(
+            "SELECT key FROM {} WHERE {} ORDER BY key ASC",
+            self.get_table_name(),
+            filter
+        );
+
+        let conn = &*self.connnection;
+        let prefix = prefix.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut stmt = conn.prepare(&query).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to set the statement: {:?}",
+                    e
+                ))
+            })?;
+
+            let mut rows =
+                stmt.query(params_from_iter(values)).await.map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to list the keys: {:?}",
+                        e
+                    ))
+                })?;
+
+            let mut keys: Vec<String> = Vec::new();
+
+            while let Some(row) = rows.next().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to iterate rows: {:?}",
+                    e
+                ))
+            })? {
+                keys.push(row.get(0).map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to get the key: {:?}",
+                        e
+                    ))
+                })?);
+            }
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store keys: {:?} | {} | {} keys",
+                duration,
+                prefix,
+                keys.len()
+            );
+
+            Ok(keys)
+        })
+    }
+
+    fn set(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<StoreModel>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let select = format!(
+            "SELECT key, value, expires_at FROM {table_name} WHERE key = ?1 AND (expires_at IS NULL OR expires_at > {now}) LIMIT 1",
+            table_name = self.get_table_name(),
+            now = NOW_MS
+        );
+        let upsert = self.upsert_query("");
+
+        let conn = &*self.connnection;
+        let key = key.to_string();
+        let ttl = ttl.map(ttl_millis);
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let value_str = value.to_string();
+
+            // Reading the previous value and writing the new one happen in
+            // one transaction, so concurrent writers can't slip in between.
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to begin the transaction: {:?}",
+                        e
+                    ))
+                })?;
+
+            let mut rows =
+                tx.query(&select, params![key.clone()]).await.map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to fetch the previous value: {:?}",
+                        e
+                    ))
+                })?;
+
+            let previous = match rows.next().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to iterate rows: {:?}",
+                    e
+                ))
+            })? {
+                Some(row) => Some(read_model(&row)?),
+                None => None,
+            };
+            drop(rows);
+
+            tx.execute(&upsert, params![key.clone(), value_str.clone(), ttl])
+                .await
+                .map_err(|_| {
+                    StoreError::QueryError(
+                        "Failed to set the value".to_string(),
+                    )
+                })?;
+
+            tx.commit().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to commit the transaction: {:?}",
+                    e
+                ))
+            })?;
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store set: {:?} | {} | {}",
+                duration,
+                key,
+                value_str
+            );
+
+            Ok(previous)
+        })
+    }
+
+    fn set_returning(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<StoreModel, StoreError>> + Send + '_>>
+    {
+        let query = self.upsert_query(" RETURNING key, value, expires_at");
+
+        let conn = &*self.connnection;
+        let key = key.to_string();
+        let ttl = ttl.map(ttl_millis);
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut stmt = conn.prepare(&query).await.map_err(|_| {
+                StoreError::QueryError(
+                    "Failed to set the statement".to_string(),
+                )
+            })?;
+
+            let row = stmt
+                .query_row(params![key.clone(), value.to_string(), ttl])
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to set the value: {:?}",
+                        e
+                    ))
+                })?;
+
+            let stored = read_model(&row)?;
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store set_returning: {:?} | {:?}",
+                duration,
+                stored
+            );
+
+            Ok(stored)
+        })
+    }
+
+    fn set_many(
+        &self,
+        entries: Vec<(String, Value, Option<u64>)>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        let upsert = self.upsert_query("");
+
+        let conn = &*self.connnection;
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            // Dropping the transaction on an early return rolls it back, so
+            // either every entry is written or none is.
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to begin the transaction: {:?}",
+                        e
+                    ))
+                })?;
+
+            let mut stmt = tx.prepare(&upsert).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to set the statement: {:?}",
+                    e
+                ))
+            })?;
+
+            for (key, value, ttl) in &entries {
+                stmt.execute(params![
+                    key.as_str(),
+                    value.to_string(),
+                    ttl.map(ttl_millis)
+                ])
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to set the value of {}: {:?}",
+                        key, e
+                    ))
+                })?;
+                stmt.reset();
+            }
+            drop(stmt);
+
+            tx.commit().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to commit the transaction: {:?}",
+                    e
+                ))
+            })?;
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store set_many: {:?} | {} entries",
+                duration,
+                entries.len()
+            );
+
+            Ok(())
+        })
+    }
+
+    fn get_with_version(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<(Value, u64)>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let conn = &*self.connnection;
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let versioned = self.read_versioned(conn, &key).await?;
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store get_with_version: {:?} | {} | {:?}",
+                duration,
+                key,
+                versioned
+            );
+
+            Ok(versioned)
+        })
+    }
+
+    fn set_if_version(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+        expected_version: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>
+    {
+        // Version 0 stands for an absent key: the write may only insert, or
+        // replace an expired entry. Otherwise it may only replace the live
+        // entry carrying the expected version.
+        let query = if expected_version == 0 {
+            self.upsert_query(&format!(
+                " WHERE expires_at IS NOT NULL AND expires_at <= {} RETURNING version",
+                NOW_MS
+            ))
+        } else {
+            format!(
+                "UPDATE {table_name} SET value = ?2, expires_at = {now} + ?3, ttl = ?3, version = version + 1 WHERE key = ?1 AND version = ?4 AND (expires_at IS NULL OR expires_at > {now}) RETURNING version",
+                table_name = self.get_table_name(),
+                now = NOW_MS
+            )
+        };
+
+        let conn = &*self.connnection;
+        let key = key.to_string();
+        let ttl = ttl.map(ttl_millis);
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut args = vec![
+                libsql::Value::from(key.clone()),
+                libsql::Value::from(value.to_string()),
+                libsql::Value::from(ttl),
+            ];
+            if expected_version > 0 {
+                args.push(libsql::Value::from(expected_version as i64));
+            }
+
+            let mut rows = conn
+                .query(&query, params_from_iter(args))
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to set the value: {:?}",
+                        e
+                    ))
+                })?;
+
+            let written = rows.next().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to iterate rows: {:?}",
+                    e
+                ))
+            })?;
+
+            let version = match written {
+                Some(row) => row.get::<u64>(0).map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to get the version: {:?}",
+                        e
+                    ))
+                })?,
+                None => {
+                    drop(rows);
+                    let actual = self
+                        .read_versioned(conn, &key)
+                        .await?
+                        .map_or(0, |(_, version)| version);
+
+                    return Err(StoreError::VersionConflict {
+                        key,
+                        expected: expected_version,
+                        actual,
+                    });
+                }
+            };
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store set_if_version: {:?} | {} | {}",
+                duration,
+                key,
+                version
+            );
+
+            Ok(version)
+        })
+    }
+
+    fn set_if_absent(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        // An existing row is only overwritten once it has expired.
+        let query = self.upsert_query(&format!(
+            " WHERE expires_at IS NOT NULL AND expires_at <= {} RETURNING key",
+            NOW_MS
+        ));
+
+        self.write_if("set_if_absent", query, key, value, ttl)
+    }
+
+    fn set_if_present(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let query = format!(
+            "UPDATE {table_name} SET value = ?2, expires_at = {now} + ?3, ttl = ?3, version = version + 1 WHERE key = ?1 AND (expires_at IS NULL OR expires_at > {now}) RETURNING key",
+            table_name = self.get_table_name(),
+            now = NOW_MS
+        );
+
+        self.write_if("set_if_present", query, key, value, ttl)
+    }
+
+    fn incr_by(
+        &self,
+        key: &str,
+        delta: i64,
+    ) -> Pin<Box<dyn Future<Output = Result<i64, StoreError>> + Send + '_>>
+    {
+        // Only integers are incremented, and only when the result stays
+        // within 64 bits; SQLite would silently switch to floating point.
+        let query = self.increment_query(
+            "CAST(value AS INTEGER) + ?2",
+            "json_type(value) = 'integer' AND (?2 >= 0 AND CAST(value AS INTEGER) <= 9223372036854775807 - ?2 OR ?2 < 0 AND CAST(value AS INTEGER) >= -9223372036854775807 - 1 - ?2)",
+        );
+
+        let conn = &*self.connnection;
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let result = self
+                .increment(conn, &query, &key, libsql::Value::from(delta))
+                .await?;
+
+            let value = match result.as_ref().and_then(Value::as_i64) {
+                Some(value) => value,
+                None => {
+                    let current = self.read_versioned(conn, &key).await?;
+                    return Err(increment_error(key, current, true));
+                }
+            };
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store incr_by: {:?} | {} | {}",
+                duration,
+                key,
+                value
+            );
+
+            Ok(value)
+        })
+    }
+
+    fn incr_by_float(
+        &self,
+        key: &str,
+        delta: f64,
+    ) -> Pin<Box<dyn Future<Output = Result<f64, StoreError>> + Send + '_>>
+    {
+        // The sum is computed here rather than in SQL, where casting a REAL
+        // to TEXT keeps only 15 significant digits.
+        let insert = self.upsert_query("");
+        let update = format!(
+            "UPDATE {table_name} SET value = ?2, version = version + 1 WHERE key = ?1",
+            table_name = self.get_table_name()
+        );
+
+        let conn = &*self.connnection;
+        let key = key.to_string();
+
+        Box::pin(async move {
+            if !delta.is_finite() {
+                return Err(StoreError::Overflow(key));
+            }
+
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to begin the transaction: {:?}",
+                        e
+                    ))
+                })?;
+
+            // A missing or expired key is (re)created holding the delta, with
+            // no TTL; a live key keeps its TTL.
+            let (fresh, value) = match self.read_versioned(&tx, &key).await? {
+                None => (true, delta),
+                Some((current, version)) => {
+                    match current.as_f64().map(|n| n + delta) {
+                        Some(sum) if sum.is_finite() => (false, sum),
+                        _ => {
+                            let current = Some((current, version));
+                            return Err(increment_error(key, current, false));
+                        }
+                    }
+                }
+            };
+
+            let mut args = vec![
+                libsql::Value::from(key.clone()),
+                libsql::Value::from(Value::from(value).to_string()),
+            ];
+            let query = if fresh {
+                args.push(libsql::Value::Null);
+                &insert
+            } else {
+                &update
+            };
+
+            tx.execute(query, params_from_iter(args))
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to increment the value: {:?}",
+                        e
+                    ))
+                })?;
+
+            tx.commit().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to commit the transaction: {:?}",
+                    e
+                ))
+            })?;
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store incr_by_float: {:?} | {} | {}",
+                duration,
+                key,
+                value
+            );
+
+            Ok(value)
+        })
+    }
+
+    fn ttl(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Option<Ttl>, StoreError>> + Send + '_>,
+    > {
+        let query = format!(
+            "SELECT expires_at - {now} FROM {table_name} WHERE key = ?1 AND (expires_at IS NULL OR expires_at > {now}) LIMIT 1",
+            table_name = self.get_table_name(),
+            now = NOW_MS
+        );
+
+        let conn = &*self.connnection;
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut stmt = conn.prepare(&query).await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to set the statement: {:?}",
+                    e
+                ))
+            })?;
+
+            let mut rows =
+                stmt.query(params![key.clone()]).await.map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to fetch the ttl: {:?}",
+                        e
+                    ))
+                })?;
+
+            let ttl = match rows.next().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to iterate rows: {:?}",
+                    e
+                ))
+            })? {
+                Some(row) => {
+                    let remaining: Option<i64> = row.get(0).map_err(|e| {
+                        StoreError::QueryError(format!(
+                            "Failed to get the ttl: {:?}",
+                            e
+                        ))
+                    })?;
+
+                    Some(match remaining {
+                        Some(ms) => Ttl::Expires(Duration::from_millis(
+                            ms.max(0) as u64,
+                        )),
+                        None => Ttl::Persistent,
+                    })
+                }
+                None => None,
+            };
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store ttl: {:?} | {} | {:?}",
+                duration,
+                key,
+                ttl
+            );
+
+            Ok(ttl)
+        })
+    }
+
+    fn expire(
+        &self,
+        key: &str,
+        ttl: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let query = format!(
+            "UPDATE {table_name} SET expires_at = {now} + ?2, ttl = ?2 WHERE key = ?1 AND (expires_at IS NULL OR expires_at > {now})",
+            table_name = self.get_table_name(),
+            now = NOW_MS
+        );
+
+        self.update_expiry("expire", query, key, Some(ttl_millis(ttl)))
+    }
+
+    fn persist(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let query = format!(
+            "UPDATE {table_name} SET expires_at = NULL, ttl = NULL WHERE key = ?1 AND (expires_at IS NULL OR expires_at > {now})",
+            table_name = self.get_table_name(),
+            now = NOW_MS
+        );
+
+        self.update_expiry("persist", query, key, None)
+    }
+
+    fn touch(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        // Entries written before the `ttl` column existed keep their
+        // expiry, since their original lifetime is unknown.
+        let query = format!(
+            "UPDATE {table_name} SET expires_at = COALESCE({now} + ttl, expires_at) WHERE key = ?1 AND (expires_at IS NULL OR expires_at > {now})",
+            table_name = self.get_table_name(),
+            now = NOW_MS
+        );
+
+        self.update_expiry("touch", query, key, None)
+    }
+
+    fn remove(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        let query = self.remove_query();
+
+        let conn = &*self.connnection;
+
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let start = Instant::now();
+
+            let mut stmt = conn.prepare(&query).await.map_err(|_| {
+                StoreError::QueryError(
+                    "Failed to set the statement".to_string(),
+                )
+            })?;
+
+            stmt.execute(params![key.clone()]).await.map_err(|_| {
                 StoreError::QueryError("Failed to remove the key".to_string())
             })?;
 
@@ -490,6 +2439,8 @@ impl Store for KyvalStore {
         let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<String>>();
 
         Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
             let start = Instant::now();
 
             let mut stmt = conn.prepare(&query).await.map_err(|_| {
@@ -509,6 +2460,32 @@ impl Store for KyvalStore {
         })
     }
 
+    fn purge_expired(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>
+    {
+        let conn = &*self.connnection;
+        let table_name = self.get_table_name();
+        let batch_size = self.sweep_batch_size;
+
+        Box::pin(async move {
+            let start = Instant::now();
+
+            let removed =
+                purge_expired_rows(conn, &self.lock, &table_name, batch_size)
+                    .await?;
+
+            let duration = start.elapsed();
+            log::debug!(
+                "Kyval store purge_expired: {:?} | {}",
+                duration,
+                removed
+            );
+
+            Ok(removed)
+        })
+    }
+
     fn clear(
         &self,
     ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
@@ -517,6 +2494,8 @@ impl Store for KyvalStore {
         let conn = &*self.connnection;
 
         Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
             conn.execute(&query, params![]).await.map_err(|_| {
                 StoreError::QueryError("Failed to clear the table".to_string())
             })?;
@@ -525,3 +2504,211 @@ impl Store for KyvalStore {
         })
     }
 }
+
+impl TransactionalStore for KyvalStore {
+    fn begin(
+        &self,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Box<dyn StoreTransaction>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let conn = &*self.connnection;
+
+        Box::pin(async move {
+            // The lock is held until the transaction ends, so that no other
+            // operation on this store runs inside it.
+            let lock = self.lock.clone().lock_owned().await;
+
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to begin the transaction: {:?}",
+                        e
+                    ))
+                })?;
+
+            log::debug!("Kyval store transaction started");
+
+            let transaction: Box<dyn StoreTransaction> =
+                Box::new(KyvalStoreTransaction {
+                    state: tokio::sync::Mutex::new(Some((tx, lock))),
+                    get_query: self.get_query(),
+                    upsert_query: self.upsert_query(""),
+                    remove_query: self.remove_query(),
+                });
+
+            Ok(transaction)
+        })
+    }
+}
+
+/// A transaction on a `KyvalStore`, holding the store's connection lock
+/// until it is committed or rolled back. Dropping it rolls it back.
+struct KyvalStoreTransaction {
+    state: tokio::sync::Mutex<
+        Option<(libsql::Transaction, tokio::sync::OwnedMutexGuard<()>)>,
+    >,
+    get_query: String,
+    upsert_query: String,
+    remove_query: String,
+}
+
+impl KyvalStoreTransaction {
+    fn finished() -> StoreError {
+        StoreError::QueryError(
+            "The transaction has already been committed or rolled back"
+                .to_string(),
+        )
+    }
+}
+
+impl StoreTransaction for KyvalStoreTransaction {
+    fn get(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Option<Value>, StoreError>> + Send + '_>,
+    > {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let state = self.state.lock().await;
+            let (tx, _) = state.as_ref().ok_or_else(Self::finished)?;
+
+            let mut rows = tx
+                .query(&self.get_query, params![key.clone()])
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to fetch the value: {:?}",
+                        e
+                    ))
+                })?;
+
+            let value = match rows.next().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to iterate rows: {:?}",
+                    e
+                ))
+            })? {
+                Some(row) => {
+                    let row_value: String = row.get(0).map_err(|e| {
+                        StoreError::QueryError(format!(
+                            "Failed to get the value: {:?}",
+                            e
+                        ))
+                    })?;
+
+                    Some(decode_value(row_value))
+                }
+                None => None,
+            };
+
+            log::debug!("Kyval store transaction get: {} | {:?}", key, value);
+
+            Ok(value)
+        })
+    }
+
+    fn set(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        let key = key.to_string();
+        let ttl = ttl.map(ttl_millis);
+
+        Box::pin(async move {
+            let state = self.state.lock().await;
+            let (tx, _) = state.as_ref().ok_or_else(Self::finished)?;
+
+            let value_str = value.to_string();
+            tx.execute(
+                &self.upsert_query,
+                params![key.clone(), value_str.clone(), ttl],
+            )
+            .await
+            .map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to set the value: {:?}",
+                    e
+                ))
+            })?;
+
+            log::debug!("Kyval store transaction set: {} | {}", key, value_str);
+
+            Ok(())
+        })
+    }
+
+    fn remove(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let state = self.state.lock().await;
+            let (tx, _) = state.as_ref().ok_or_else(Self::finished)?;
+
+            tx.execute(&self.remove_query, params![key.clone()])
+                .await
+                .map_err(|e| {
+                    StoreError::QueryError(format!(
+                        "Failed to remove the key: {:?}",
+                        e
+                    ))
+                })?;
+
+            log::debug!("Kyval store transaction remove: {}", key);
+
+            Ok(())
+        })
+    }
+
+    fn commit(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        Box::pin(async move {
+            let (tx, _lock) =
+                self.state.lock().await.take().ok_or_else(Self::finished)?;
+
+            tx.commit().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to commit the transaction: {:?}",
+                    e
+                ))
+            })?;
+
+            log::debug!("Kyval store transaction committed");
+
+            Ok(())
+        })
+    }
+
+    fn rollback(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        Box::pin(async move {
+            let (tx, _lock) =
+                self.state.lock().await.take().ok_or_else(Self::finished)?;
+
+            tx.rollback().await.map_err(|e| {
+                StoreError::QueryError(format!(
+                    "Failed to roll back the transaction: {:?}",
+                    e
+                ))
+            })?;
+
+            log::debug!("Kyval store transaction rolled back");
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,811 @@
+// Copyright © 2024 Aris Ripandi - All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::ops::Bound;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use crate::store::{
+    decode_cursor, encode_cursor, increment_error, now_ms, prefix_upper_bound,
+    ttl_millis,
+};
+use crate::{
+    EntryMeta, ScanOptions, ScanPage, Store, StoreError, StoreModel, Ttl,
+};
+
+/// A stored value and its bookkeeping. Times are UTC epoch milliseconds.
+#[derive(Debug, Clone)]
+struct Entry {
+    value: Value,
+    expires_at: Option<u64>,
+    ttl: Option<u64>,
+    version: u64,
+    created_at: u64,
+    updated_at: u64,
+}
+
+impl Entry {
+    fn is_live(&self, now: u64) -> bool {
+        self.expires_at.map_or(true, |expires_at| expires_at > now)
+    }
+
+    fn model(&self, key: &str) -> StoreModel {
+        StoreModel {
+            key: key.to_string(),
+            value: self.value.clone(),
+            expires_at: self.expires_at,
+        }
+    }
+}
+
+/// A `Store` that keeps every entry in process memory.
+///
+/// Entries live in an ordered map, so listing and scanning return keys in the
+/// same order as `KyvalStore`, and expired entries are treated as absent in
+/// the same way. Expired entries are only dropped when overwritten or by
+/// `purge_expired`; there is no background sweeper.
+///
+/// Nothing is persisted, and the store does not support `Kyval::transaction`.
+///
+/// # Examples
+///
+/// ```
+/// # use kyval::Kyval;
+/// # use kyval::adapter::MemoryStore;
+/// #[tokio::main]
+/// async fn main() {
+///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+///
+///     kyval.set("key", "value").await.unwrap();
+///     assert!(kyval.exists("key").await.unwrap());
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: RwLock<BTreeMap<String, Entry>>,
+    /// Highest version of any removed entry. New entries start above it, so
+    /// a recreated key never reuses a version. Only changed while holding
+    /// the write lock on `entries`.
+    removed_version: AtomicU64,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // No operation can leave the map half-updated, so a poisoned lock is
+    // still safe to use.
+    fn read(&self) -> RwLockReadGuard<'_, BTreeMap<String, Entry>> {
+        self.entries.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<String, Entry>> {
+        self.entries.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Raises `removed_version` to cover an entry that was just removed.
+    fn forget(&self, entry: Option<Entry>) {
+        if let Some(entry) = entry {
+            self.removed_version
+                .fetch_max(entry.version, Ordering::Relaxed);
+        }
+    }
+
+    /// Writes `value` under `key` with a TTL in seconds, and returns the new
+    /// entry. Like `KyvalStore`, an overwrite bumps the version and keeps the
+    /// creation time, unless the entry being replaced had expired.
+    fn put<'a>(
+        &self,
+        entries: &'a mut BTreeMap<String, Entry>,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+        now: u64,
+    ) -> &'a Entry {
+        let ttl = ttl.map(|ttl| ttl_millis(ttl) as u64);
+        let expires_at = ttl.map(|ttl| now + ttl);
+
+        let (version, created_at) = match entries.get(key) {
+            Some(entry) if entry.is_live(now) => {
+                (entry.version + 1, entry.created_at)
+            }
+            Some(entry) => (entry.version + 1, now),
+            None => (self.removed_version.load(Ordering::Relaxed) + 1, now),
+        };
+
+        entries.insert(
+            key.to_string(),
+            Entry {
+                value,
+                expires_at,
+                ttl,
+                version,
+                created_at,
+                updated_at: now,
+            },
+        );
+
+        &entries[key]
+    }
+}
+
+/// Returns the entry stored under `key`, unless it has expired.
+fn live<'a>(
+    entries: &'a BTreeMap<String, Entry>,
+    key: &str,
+    now: u64,
+) -> Option<&'a Entry> {
+    entries.get(key).filter(|entry| entry.is_live(now))
+}
+
+/// Returns the entry stored under `key` for updating, unless it has expired.
+fn live_mut<'a>(
+    entries: &'a mut BTreeMap<String, Entry>,
+    key: &str,
+    now: u64,
+) -> Option<&'a mut Entry> {
+    entries.get_mut(key).filter(|entry| entry.is_live(now))
+}
+
+/// Returns the tighter of two lower bounds.
+fn max_lower(a: Bound<String>, b: Bound<String>) -> Bound<String> {
+    match (&a, &b) {
+        (Bound::Unbounded, _) => b,
+        (_, Bound::Unbounded) => a,
+        (
+            Bound::Included(x) | Bound::Excluded(x),
+            Bound::Included(y) | Bound::Excluded(y),
+        ) => {
+            if x > y || (x == y && matches!(a, Bound::Excluded(_))) {
+                a
+            } else {
+                b
+            }
+        }
+    }
+}
+
+/// Returns the tighter of two upper bounds.
+fn min_upper(a: Bound<String>, b: Bound<String>) -> Bound<String> {
+    match (&a, &b) {
+        (Bound::Unbounded, _) => b,
+        (_, Bound::Unbounded) => a,
+        (
+            Bound::Included(x) | Bound::Excluded(x),
+            Bound::Included(y) | Bound::Excluded(y),
+        ) => {
+            if x < y || (x == y && matches!(a, Bound::Excluded(_))) {
+                a
+            } else {
+                b
+            }
+        }
+    }
+}
+
+fn above(key: &str, lower: &Bound<String>) -> bool {
+    match lower {
+        Bound::Included(bound) => key >= bound.as_str(),
+        Bound::Excluded(bound) => key > bound.as_str(),
+        Bound::Unbounded => true,
+    }
+}
+
+fn below(key: &str, upper: &Bound<String>) -> bool {
+    match upper {
+        Bound::Included(bound) => key <= bound.as_str(),
+        Bound::Excluded(bound) => key < bound.as_str(),
+        Bound::Unbounded => true,
+    }
+}
+
+/// Iterates over the entries whose key starts with `prefix`, in key order.
+fn with_prefix<'a>(
+    entries: &'a BTreeMap<String, Entry>,
+    prefix: &'a str,
+) -> impl Iterator<Item = (&'a String, &'a Entry)> {
+    entries
+        .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+        .take_while(move |(key, _)| key.starts_with(prefix))
+}
+
+impl Store for MemoryStore {
+    fn initialize(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        Box::pin(async move { Ok(()) })
+    }
+
+    fn get(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Option<Value>, StoreError>> + Send + '_>,
+    > {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let entries = self.read();
+            Ok(live(&entries, &key, now_ms()).map(|entry| entry.value.clone()))
+        })
+    }
+
+    fn get_many(
+        &self,
+        keys: &[&str],
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<HashMap<String, Value>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<String>>();
+
+        Box::pin(async move {
+            let entries = self.read();
+            let now = now_ms();
+
+            Ok(keys
+                .into_iter()
+                .filter_map(|key| {
+                    let value = live(&entries, &key, now)?.value.clone();
+                    Some((key, value))
+                })
+                .collect())
+        })
+    }
+
+    fn exists(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let entries = self.read();
+            Ok(live(&entries, &key, now_ms()).is_some())
+        })
+    }
+
+    fn metadata(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<EntryMeta>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let entries = self.read();
+
+            Ok(live(&entries, &key, now_ms()).map(|entry| EntryMeta {
+                key: key.clone(),
+                created_at: Some(entry.created_at),
+                updated_at: Some(entry.updated_at),
+                expires_at: entry.expires_at,
+                size: entry.value.to_string().len() as u64,
+                version: entry.version,
+            }))
+        })
+    }
+
+    fn list(
+        &self,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Vec<StoreModel>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        Box::pin(async move {
+            let entries = self.read();
+            let now = now_ms();
+
+            Ok(entries
+                .iter()
+                .filter(|(_, entry)| entry.is_live(now))
+                .map(|(key, entry)| entry.model(key))
+                .collect())
+        })
+    }
+
+    fn scan(
+        &self,
+        options: ScanOptions,
+    ) -> Pin<Box<dyn Future<Output = Result<ScanPage, StoreError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let mut lower = Bound::Unbounded;
+            let mut upper = Bound::Unbounded;
+
+            if let Some(prefix) = options.prefix {
+                if let Some(bound) = prefix_upper_bound(&prefix) {
+                    upper = min_upper(upper, Bound::Excluded(bound));
+                }
+                lower = max_lower(lower, Bound::Included(prefix));
+            }
+            if let Some(start) = options.start {
+                lower = max_lower(lower, Bound::Included(start));
+            }
+            if let Some(end) = options.end {
+                upper = min_upper(upper, Bound::Excluded(end));
+            }
+            if let Some(cursor) = options.cursor.as_deref() {
+                let after = decode_cursor(cursor)?;
+                if options.reverse {
+                    upper = min_upper(upper, Bound::Excluded(after));
+                } else {
+                    lower = max_lower(lower, Bound::Excluded(after));
+                }
+            }
+
+            let limit = options.limit.max(1);
+            let entries = self.read();
+            let now = now_ms();
+
+            // Only one bound is handed to `range`, which panics on inverted
+            // ranges; the other one ends the walk instead.
+            let walk: Box<dyn Iterator<Item = (&String, &Entry)>> =
+                if options.reverse {
+                    Box::new(
+                        entries
+                            .range((Bound::Unbounded, upper))
+                            .rev()
+                            .take_while(|(key, _)| above(key, &lower)),
+                    )
+                } else {
+                    Box::new(
+                        entries
+                            .range((lower.clone(), Bound::Unbounded))
+                            .take_while(|(key, _)| below(key, &upper)),
+                    )
+                };
+
+            let mut items: Vec<StoreModel> = walk
+                .filter(|(_, entry)| entry.is_live(now))
+                .take(limit + 1)
+                .map(|(key, entry)| entry.model(key))
+                .collect();
+
+            let cursor = if items.len() > limit {
+                items.truncate(limit);
+                items.last().map(|item| encode_cursor(&item.key))
+            } else {
+                None
+            };
+
+            Ok(ScanPage { items, cursor })
+        })
+    }
+
+    fn count(
+        &self,
+        prefix: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>
+    {
+        let prefix = prefix.to_string();
+
+        Box::pin(async move {
+            let entries = self.read();
+            let now = now_ms();
+
+            Ok(with_prefix(&entries, &prefix)
+                .filter(|(_, entry)| entry.is_live(now))
+                .count() as u64)
+        })
+    }
+
+    fn keys(
+        &self,
+        prefix: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Vec<String>, StoreError>> + Send + '_>,
+    > {
+        let prefix = prefix.to_string();
+
+        Box::pin(async move {
+            let entries = self.read();
+            let now = now_ms();
+
+            Ok(with_prefix(&entries, &prefix)
+                .filter(|(_, entry)| entry.is_live(now))
+                .map(|(key, _)| key.clone())
+                .collect())
+        })
+    }
+
+    fn set(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<StoreModel>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            let now = now_ms();
+
+            let previous = live(&entries, &key, now).map(|e| e.model(&key));
+            self.put(&mut entries, &key, value, ttl, now);
+
+            Ok(previous)
+        })
+    }
+
+    fn set_returning(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<StoreModel, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            Ok(self
+                .put(&mut entries, &key, value, ttl, now_ms())
+                .model(&key))
+        })
+    }
+
+    fn set_many(
+        &self,
+        entries: Vec<(String, Value, Option<u64>)>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        Box::pin(async move {
+            let mut map = self.write();
+            let now = now_ms();
+
+            for (key, value, ttl) in entries {
+                self.put(&mut map, &key, value, ttl, now);
+            }
+
+            Ok(())
+        })
+    }
+
+    fn get_with_version(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<(Value, u64)>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let entries = self.read();
+
+            Ok(live(&entries, &key, now_ms())
+                .map(|entry| (entry.value.clone(), entry.version)))
+        })
+    }
+
+    fn set_if_version(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+        expected_version: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            let now = now_ms();
+
+            // Version 0 stands for an absent key.
+            let actual = live(&entries, &key, now).map_or(0, |e| e.version);
+            if actual != expected_version {
+                return Err(StoreError::VersionConflict {
+                    key,
+                    expected: expected_version,
+                    actual,
+                });
+            }
+
+            Ok(self.put(&mut entries, &key, value, ttl, now).version)
+        })
+    }
+
+    fn set_if_absent(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            let now = now_ms();
+
+            if live(&entries, &key, now).is_some() {
+                return Ok(false);
+            }
+
+            self.put(&mut entries, &key, value, ttl, now);
+            Ok(true)
+        })
+    }
+
+    fn set_if_present(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            let now = now_ms();
+
+            if live(&entries, &key, now).is_none() {
+                return Ok(false);
+            }
+
+            self.put(&mut entries, &key, value, ttl, now);
+            Ok(true)
+        })
+    }
+
+    fn incr_by(
+        &self,
+        key: &str,
+        delta: i64,
+    ) -> Pin<Box<dyn Future<Output = Result<i64, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            let now = now_ms();
+
+            let Some(entry) = live_mut(&mut entries, &key, now) else {
+                self.put(&mut entries, &key, Value::from(delta), None, now);
+                return Ok(delta);
+            };
+
+            let Some(sum) =
+                entry.value.as_i64().and_then(|n| n.checked_add(delta))
+            else {
+                let current = Some((entry.value.clone(), entry.version));
+                return Err(increment_error(key, current, true));
+            };
+
+            entry.value = Value::from(sum);
+            entry.version += 1;
+            entry.updated_at = now;
+
+            Ok(sum)
+        })
+    }
+
+    fn incr_by_float(
+        &self,
+        key: &str,
+        delta: f64,
+    ) -> Pin<Box<dyn Future<Output = Result<f64, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            if !delta.is_finite() {
+                return Err(StoreError::Overflow(key));
+            }
+
+            let mut entries = self.write();
+            let now = now_ms();
+
+            let Some(entry) = live_mut(&mut entries, &key, now) else {
+                self.put(&mut entries, &key, Value::from(delta), None, now);
+                return Ok(delta);
+            };
+
+            let Some(sum) = entry
+                .value
+                .as_f64()
+                .map(|n| n + delta)
+                .filter(|sum| sum.is_finite())
+            else {
+                let current = Some((entry.value.clone(), entry.version));
+                return Err(increment_error(key, current, false));
+            };
+
+            entry.value = Value::from(sum);
+            entry.version += 1;
+            entry.updated_at = now;
+
+            Ok(sum)
+        })
+    }
+
+    fn ttl(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Option<Ttl>, StoreError>> + Send + '_>,
+    > {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let entries = self.read();
+            let now = now_ms();
+
+            Ok(
+                live(&entries, &key, now).map(|entry| match entry.expires_at {
+                    Some(expires_at) => {
+                        Ttl::Expires(Duration::from_millis(expires_at - now))
+                    }
+                    None => Ttl::Persistent,
+                }),
+            )
+        })
+    }
+
+    fn expire(
+        &self,
+        key: &str,
+        ttl: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+        let ttl = ttl_millis(ttl) as u64;
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            let now = now_ms();
+
+            Ok(match live_mut(&mut entries, &key, now) {
+                Some(entry) => {
+                    entry.expires_at = Some(now + ttl);
+                    entry.ttl = Some(ttl);
+                    entry.updated_at = now;
+                    true
+                }
+                None => false,
+            })
+        })
+    }
+
+    fn persist(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            let now = now_ms();
+
+            Ok(match live_mut(&mut entries, &key, now) {
+                Some(entry) => {
+                    entry.expires_at = None;
+                    entry.ttl = None;
+                    entry.updated_at = now;
+                    true
+                }
+                None => false,
+            })
+        })
+    }
+
+    fn touch(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            let now = now_ms();
+
+            Ok(match live_mut(&mut entries, &key, now) {
+                Some(entry) => {
+                    if let Some(ttl) = entry.ttl {
+                        entry.expires_at = Some(now + ttl);
+                    }
+                    entry.updated_at = now;
+                    true
+                }
+                None => false,
+            })
+        })
+    }
+
+    fn remove(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            self.forget(entries.remove(&key));
+            Ok(())
+        })
+    }
+
+    fn remove_many(
+        &self,
+        keys: &[&str],
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<String>>();
+
+        Box::pin(async move {
+            let mut entries = self.write();
+            for key in keys {
+                self.forget(entries.remove(&key));
+            }
+            Ok(())
+        })
+    }
+
+    fn purge_expired(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let mut entries = self.write();
+            let now = now_ms();
+
+            let expired = entries
+                .iter()
+                .filter(|(_, entry)| !entry.is_live(now))
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<String>>();
+            for key in &expired {
+                self.forget(entries.remove(key));
+            }
+
+            Ok(expired.len() as u64)
+        })
+    }
+
+    fn clear(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        Box::pin(async move {
+            let mut entries = self.write();
+            for (_, entry) in std::mem::take(&mut *entries) {
+                self.forget(Some(entry));
+            }
+            Ok(())
+        })
+    }
+}
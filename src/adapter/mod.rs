@@ -1,2 +1,10 @@
+#[cfg(feature = "libsql")]
 mod libsql;
+#[cfg(feature = "libsql")]
 pub use libsql::*;
+
+mod memory;
+pub use memory::*;
+
+mod tiered;
+pub use tiered::*;
@@ -0,0 +1,920 @@
+// Copyright © 2024 Aris Ripandi - All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::store::{now_ms, ttl_millis};
+use crate::{
+    EntryMeta, ScanOptions, ScanPage, Store, StoreError, StoreModel, Ttl,
+};
+
+/// Default number of entries cached by a `TieredStore`.
+pub const DEFAULT_CACHE_CAPACITY: usize = 1000;
+
+/// When a `TieredStore` writes to its backing store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WritePolicy {
+    /// Every write reaches the backing store before it returns.
+    #[default]
+    WriteThrough,
+    /// Plain writes are buffered and reach the backing store on
+    /// `Store::flush`, once the buffer is full, or before any operation that
+    /// has to be answered by the backing store. Buffered writes are lost, and
+    /// a warning is logged, if the store is dropped without flushing.
+    WriteBack,
+}
+
+/// A buffered write-back entry. Expiry is tracked locally until the entry is
+/// flushed.
+#[derive(Debug, Clone, PartialEq)]
+struct Pending {
+    value: Value,
+    expires_at: Option<Instant>,
+}
+
+impl Pending {
+    fn new(value: Value, ttl: Option<u64>) -> Self {
+        let expires_at = ttl.and_then(|ttl| {
+            Instant::now()
+                .checked_add(Duration::from_millis(ttl_millis(ttl) as u64))
+        });
+
+        Self { value, expires_at }
+    }
+
+    /// Returns the remaining lifetime, `Some(None)` if the entry does not
+    /// expire, or `None` if it already has.
+    fn remaining(&self) -> Option<Option<Duration>> {
+        match self.expires_at {
+            Some(at) => {
+                let remaining = at.saturating_duration_since(Instant::now());
+                (!remaining.is_zero()).then_some(Some(remaining))
+            }
+            None => Some(None),
+        }
+    }
+}
+
+/// Recency of cached keys and the write-back buffer.
+#[derive(Debug, Default)]
+struct TierState {
+    tick: u64,
+    recency: BTreeMap<u64, String>,
+    ticks: HashMap<String, u64>,
+    pending: HashMap<String, Pending>,
+}
+
+impl TierState {
+    /// Marks `key` as the most recently used cached key.
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        if let Some(tick) = self.ticks.insert(key.to_string(), self.tick) {
+            self.recency.remove(&tick);
+        }
+        self.recency.insert(self.tick, key.to_string());
+    }
+
+    /// Marks `key` as used if it is cached.
+    fn hit(&mut self, key: &str) {
+        if self.ticks.contains_key(key) {
+            self.touch(key);
+        }
+    }
+
+    fn forget(&mut self, key: &str) {
+        if let Some(tick) = self.ticks.remove(key) {
+            self.recency.remove(&tick);
+        }
+    }
+
+    /// Stops tracking the least recently used keys beyond `capacity`, and
+    /// returns them so they can be dropped from the cache.
+    fn evict(&mut self, capacity: usize) -> Vec<String> {
+        let mut evicted = Vec::new();
+        while self.ticks.len() > capacity {
+            let Some((_, key)) = self.recency.pop_first() else {
+                break;
+            };
+            self.ticks.remove(&key);
+            evicted.push(key);
+        }
+        evicted
+    }
+}
+
+/// Converts a remaining lifetime into a TTL in whole seconds, rounded up so
+/// that an entry never expires early in the backing store.
+fn ttl_secs(remaining: Option<Duration>) -> Option<u64> {
+    remaining.map(|d| d.as_millis().div_ceil(1000) as u64)
+}
+
+/// A `Store` that caches a backing store (`L2`), such as a remote
+/// `KyvalStore`, in a faster one (`L1`), such as a `MemoryStore`.
+///
+/// Reads of cached keys are answered by `L1` alone. `L1` holds at most
+/// `capacity` entries, evicting the least recently used first, and an entry
+/// stays cached no longer than its own TTL or the optional cache TTL,
+/// whichever is shorter.
+///
+/// Writes either go through to `L2` immediately or, with
+/// `WritePolicy::WriteBack`, are buffered until flushed. `remove`,
+/// `remove_many` and `clear` always take effect on both tiers at once.
+/// Operations that `L1` cannot answer on its own, such as `list`, `scan`,
+/// counters and conditional writes, go to `L2` after flushing any buffered
+/// writes.
+///
+/// Only writes made through this store keep the cache fresh: with other
+/// writers sharing `L2`, set a cache TTL to bound staleness. Transactions are
+/// not supported.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use kyval::Kyval;
+/// # use kyval::adapter::{MemoryStore, TieredStore};
+/// # #[cfg(feature = "libsql")]
+/// #[tokio::main]
+/// async fn main() {
+/// #   use kyval::adapter::KyvalStoreBuilder;
+///     let remote = KyvalStoreBuilder::new()
+///         .uri("libsql://example.turso.io")
+///         .token("token")
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     let store = TieredStore::new(MemoryStore::new(), remote)
+///         .capacity(10_000)
+///         .cache_ttl(30);
+///
+///     let kyval = Kyval::try_new(store).await.unwrap();
+/// }
+/// # #[cfg(not(feature = "libsql"))]
+/// # fn main() {}
+/// ```
+pub struct TieredStore<L1, L2> {
+    l1: L1,
+    l2: L2,
+    capacity: usize,
+    cache_ttl: Option<u64>,
+    policy: WritePolicy,
+    state: Mutex<TierState>,
+    // Serializes everything but cache hits, so that a read filling the cache
+    // cannot race a write and cache a stale value.
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl<L1: Store, L2: Store> TieredStore<L1, L2> {
+    pub fn new(l1: L1, l2: L2) -> Self {
+        Self {
+            l1,
+            l2,
+            capacity: DEFAULT_CACHE_CAPACITY,
+            cache_ttl: None,
+            policy: WritePolicy::default(),
+            state: Mutex::new(TierState::default()),
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Sets how many entries `L1` may hold. Under `WritePolicy::WriteBack`
+    /// this also bounds the number of buffered writes.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Sets how long, in seconds, an entry may stay cached in `L1`.
+    pub fn cache_ttl(mut self, ttl: u64) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets when writes reach `L2`.
+    pub fn write_policy(mut self, policy: WritePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    fn state(&self) -> MutexGuard<'_, TierState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns a buffered write for `key`: `Some(Some(value))` if it is live,
+    /// `Some(None)` if it has expired, or `None` if there is none.
+    fn pending(&self, key: &str) -> Option<Option<Value>> {
+        let state = self.state();
+        let pending = state.pending.get(key)?;
+        Some(pending.remaining().map(|_| pending.value.clone()))
+    }
+
+    /// Writes the buffered writes to `L2`. The caller must hold `lock`.
+    ///
+    /// The writes stay buffered, and so visible to reads that do not take
+    /// `lock`, until `L2` has them; on failure they are kept for the next
+    /// flush.
+    async fn flush_pending(&self) -> Result<(), StoreError> {
+        let pending = self.state().pending.clone();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = Vec::new();
+        let mut expired = Vec::new();
+        for (key, entry) in &pending {
+            match entry.remaining() {
+                Some(remaining) => entries.push((
+                    key.clone(),
+                    entry.value.clone(),
+                    ttl_secs(remaining),
+                )),
+                // The last write has expired, so the key is absent.
+                None => expired.push(key.as_str()),
+            }
+        }
+
+        self.l2.set_many(entries).await?;
+        self.l2.remove_many(&expired).await?;
+
+        // Writes buffered since the flush started are kept for the next one.
+        let mut state = self.state();
+        for (key, entry) in pending {
+            if state.pending.get(&key) == Some(&entry) {
+                state.pending.remove(&key);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Caches `value` in `L1` with the shorter of `ttl` and the cache TTL,
+    /// evicting the least recently used entries beyond capacity.
+    async fn fill(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Result<(), StoreError> {
+        let ttl = match (ttl, self.cache_ttl) {
+            (Some(ttl), Some(cache_ttl)) => Some(ttl.min(cache_ttl)),
+            (ttl, cache_ttl) => ttl.or(cache_ttl),
+        };
+
+        if ttl == Some(0) {
+            return self.invalidate(&[key]).await;
+        }
+
+        self.l1.set(key, value, ttl).await?;
+
+        let evicted = {
+            let mut state = self.state();
+            state.touch(key);
+            state.evict(self.capacity)
+        };
+
+        if !evicted.is_empty() {
+            let evicted: Vec<&str> =
+                evicted.iter().map(|k| k.as_str()).collect();
+            self.l1.remove_many(&evicted).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Drops `keys` from `L1`.
+    async fn invalidate(&self, keys: &[&str]) -> Result<(), StoreError> {
+        {
+            let mut state = self.state();
+            for key in keys {
+                state.forget(key);
+            }
+        }
+
+        self.l1.remove_many(keys).await
+    }
+
+    /// Reads `key` along with its expiry from `L2` in a single request. No
+    /// key with `key` as a prefix sorts before it, so a one entry scan finds
+    /// it if it is there.
+    async fn read_l2(
+        &self,
+        key: &str,
+    ) -> Result<Option<StoreModel>, StoreError> {
+        let page = self
+            .l2
+            .scan(ScanOptions::new().prefix(key).limit(1))
+            .await?;
+
+        Ok(page.items.into_iter().find(|model| model.key == key))
+    }
+
+    /// Reads the entry currently stored under `key`, buffered or not. `L1`
+    /// is skipped, since the cache TTL may have shortened its expiry.
+    async fn read_model(
+        &self,
+        key: &str,
+    ) -> Result<Option<StoreModel>, StoreError> {
+        let current = self.state().pending.get(key).map(|pending| {
+            pending.remaining().map(|remaining| StoreModel {
+                key: key.to_string(),
+                value: pending.value.clone(),
+                expires_at: remaining.map(|d| now_ms() + d.as_millis() as u64),
+            })
+        });
+
+        match current {
+            Some(current) => Ok(current),
+            None => self.read_l2(key).await,
+        }
+    }
+
+    /// Buffers writes under `WritePolicy::WriteBack`, flushing the buffer
+    /// first if they would overflow it. Either every write is accepted, or
+    /// none is and the error is returned. The caller must hold `lock`.
+    async fn buffer(
+        &self,
+        entries: &[(String, Value, Option<u64>)],
+    ) -> Result<(), StoreError> {
+        let overflow = {
+            let state = self.state();
+            let added = entries
+                .iter()
+                .filter(|(key, _, _)| !state.pending.contains_key(key))
+                .count();
+            state.pending.len() + added > self.capacity
+        };
+
+        if overflow {
+            self.flush_pending().await?;
+        }
+
+        // Too many to buffer at all, so they go straight through.
+        if entries.len() > self.capacity {
+            return self.l2.set_many(entries.to_vec()).await;
+        }
+
+        let mut state = self.state();
+        for (key, value, ttl) in entries {
+            let pending = Pending::new(value.clone(), *ttl);
+            state.pending.insert(key.clone(), pending);
+        }
+
+        Ok(())
+    }
+
+    /// Writes `value` under `key` according to the write policy, and caches
+    /// it. The caller must hold `lock`.
+    async fn write(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Result<(), StoreError> {
+        match self.policy {
+            WritePolicy::WriteThrough => {
+                self.l2.set(key, value.clone(), ttl).await?;
+            }
+            WritePolicy::WriteBack => {
+                self.buffer(&[(key.to_string(), value.clone(), ttl)])
+                    .await?;
+            }
+        }
+
+        self.fill(key, value, ttl).await
+    }
+
+    /// Runs an operation that only `L2` can answer, after flushing buffered
+    /// writes, and drops `key` from the cache since it may have changed.
+    async fn through<T, Fut>(
+        &self,
+        key: Option<&str>,
+        operation: impl FnOnce() -> Fut,
+    ) -> Result<T, StoreError>
+    where
+        Fut: Future<Output = Result<T, StoreError>>,
+    {
+        let _lock = self.lock.lock().await;
+        self.flush_pending().await?;
+
+        let result = operation().await;
+
+        if let Some(key) = key {
+            self.invalidate(&[key]).await?;
+        }
+
+        result
+    }
+}
+
+impl<L1, L2> Drop for TieredStore<L1, L2> {
+    fn drop(&mut self) {
+        let state =
+            self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if !state.pending.is_empty() {
+            log::warn!(
+                "TieredStore dropped with {} buffered writes that were never flushed",
+                state.pending.len()
+            );
+        }
+    }
+}
+
+impl<L1: Store, L2: Store> Store for TieredStore<L1, L2> {
+    fn initialize(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        Box::pin(async move {
+            self.l1.initialize().await?;
+            self.l2.initialize().await
+        })
+    }
+
+    fn get(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Option<Value>, StoreError>> + Send + '_>,
+    > {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            if let Some(pending) = self.pending(&key) {
+                return Ok(pending);
+            }
+            if let Some(value) = self.l1.get(&key).await? {
+                self.state().hit(&key);
+                return Ok(Some(value));
+            }
+
+            let _lock = self.lock.lock().await;
+
+            // Another task may have filled the cache while this one waited.
+            if let Some(pending) = self.pending(&key) {
+                return Ok(pending);
+            }
+            if let Some(value) = self.l1.get(&key).await? {
+                self.state().hit(&key);
+                return Ok(Some(value));
+            }
+
+            let Some(model) = self.read_l2(&key).await? else {
+                return Ok(None);
+            };
+
+            // Round down, so the cached copy never outlives the original.
+            let ttl = model
+                .expires_at
+                .map(|expires_at| expires_at.saturating_sub(now_ms()) / 1000);
+            self.fill(&key, model.value.clone(), ttl).await?;
+
+            Ok(Some(model.value))
+        })
+    }
+
+    fn get_many(
+        &self,
+        keys: &[&str],
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<HashMap<String, Value>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<String>>();
+
+        Box::pin(async move {
+            let mut found = HashMap::new();
+            let mut uncached = Vec::new();
+
+            for key in &keys {
+                match self.pending(key) {
+                    Some(Some(value)) => {
+                        found.insert(key.clone(), value);
+                    }
+                    Some(None) => {}
+                    None => uncached.push(key.as_str()),
+                }
+            }
+
+            let cached = self.l1.get_many(&uncached).await?;
+            {
+                let mut state = self.state();
+                for key in cached.keys() {
+                    state.hit(key);
+                }
+            }
+
+            // Misses are read in one batch, without the expiry needed to
+            // cache them.
+            let missed: Vec<&str> = uncached
+                .into_iter()
+                .filter(|key| !cached.contains_key(*key))
+                .collect();
+            if !missed.is_empty() {
+                found.extend(self.l2.get_many(&missed).await?);
+            }
+            found.extend(cached);
+
+            Ok(found)
+        })
+    }
+
+    fn exists(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            if let Some(pending) = self.pending(&key) {
+                return Ok(pending.is_some());
+            }
+            if self.l1.exists(&key).await? {
+                return Ok(true);
+            }
+
+            self.l2.exists(&key).await
+        })
+    }
+
+    fn metadata(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<EntryMeta>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let key = key.to_string();
+
+        Box::pin(
+            async move { self.through(None, || self.l2.metadata(&key)).await },
+        )
+    }
+
+    fn list(
+        &self,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Vec<StoreModel>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        Box::pin(async move { self.through(None, || self.l2.list()).await })
+    }
+
+    fn scan(
+        &self,
+        options: ScanOptions,
+    ) -> Pin<Box<dyn Future<Output = Result<ScanPage, StoreError>> + Send + '_>>
+    {
+        Box::pin(
+            async move { self.through(None, || self.l2.scan(options)).await },
+        )
+    }
+
+    fn count(
+        &self,
+        prefix: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>
+    {
+        let prefix = prefix.to_string();
+
+        Box::pin(
+            async move { self.through(None, || self.l2.count(&prefix)).await },
+        )
+    }
+
+    fn keys(
+        &self,
+        prefix: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Vec<String>, StoreError>> + Send + '_>,
+    > {
+        let prefix = prefix.to_string();
+
+        Box::pin(
+            async move { self.through(None, || self.l2.keys(&prefix)).await },
+        )
+    }
+
+    fn set(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<StoreModel>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let previous = match self.policy {
+                WritePolicy::WriteThrough => {
+                    self.l2.set(&key, value.clone(), ttl).await?
+                }
+                WritePolicy::WriteBack => {
+                    let previous = self.read_model(&key).await?;
+                    self.write(&key, value.clone(), ttl).await?;
+                    return Ok(previous);
+                }
+            };
+
+            self.fill(&key, value, ttl).await?;
+
+            Ok(previous)
+        })
+    }
+
+    fn set_returning(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<StoreModel, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let model = match self.policy {
+                WritePolicy::WriteThrough => {
+                    self.l2.set_returning(&key, value.clone(), ttl).await?
+                }
+                WritePolicy::WriteBack => {
+                    self.write(&key, value.clone(), ttl).await?;
+                    return Ok(StoreModel {
+                        key,
+                        value,
+                        expires_at: ttl
+                            .map(|ttl| now_ms() + ttl_millis(ttl) as u64),
+                    });
+                }
+            };
+
+            self.fill(&key, value, ttl).await?;
+
+            Ok(model)
+        })
+    }
+
+    fn set_many(
+        &self,
+        entries: Vec<(String, Value, Option<u64>)>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            match self.policy {
+                WritePolicy::WriteThrough => {
+                    self.l2.set_many(entries.clone()).await?;
+                }
+                WritePolicy::WriteBack => self.buffer(&entries).await?,
+            }
+
+            for (key, value, ttl) in entries {
+                self.fill(&key, value, ttl).await?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn get_with_version(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<(Value, u64)>, StoreError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            self.through(None, || self.l2.get_with_version(&key)).await
+        })
+    }
+
+    fn set_if_version(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+        expected_version: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            self.through(Some(&key), || {
+                self.l2.set_if_version(&key, value, ttl, expected_version)
+            })
+            .await
+        })
+    }
+
+    fn set_if_absent(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            self.through(Some(&key), || self.l2.set_if_absent(&key, value, ttl))
+                .await
+        })
+    }
+
+    fn set_if_present(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            self.through(Some(&key), || {
+                self.l2.set_if_present(&key, value, ttl)
+            })
+            .await
+        })
+    }
+
+    fn incr_by(
+        &self,
+        key: &str,
+        delta: i64,
+    ) -> Pin<Box<dyn Future<Output = Result<i64, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            self.through(Some(&key), || self.l2.incr_by(&key, delta))
+                .await
+        })
+    }
+
+    fn incr_by_float(
+        &self,
+        key: &str,
+        delta: f64,
+    ) -> Pin<Box<dyn Future<Output = Result<f64, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            self.through(Some(&key), || self.l2.incr_by_float(&key, delta))
+                .await
+        })
+    }
+
+    fn ttl(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Option<Ttl>, StoreError>> + Send + '_>,
+    > {
+        let key = key.to_string();
+
+        Box::pin(async move { self.through(None, || self.l2.ttl(&key)).await })
+    }
+
+    fn expire(
+        &self,
+        key: &str,
+        ttl: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            self.through(Some(&key), || self.l2.expire(&key, ttl)).await
+        })
+    }
+
+    fn persist(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            self.through(Some(&key), || self.l2.persist(&key)).await
+        })
+    }
+
+    fn touch(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            self.through(Some(&key), || self.l2.touch(&key)).await
+        })
+    }
+
+    fn remove(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            self.state().pending.remove(&key);
+            self.l2.remove(&key).await?;
+            self.invalidate(&[&key]).await
+        })
+    }
+
+    fn remove_many(
+        &self,
+        keys: &[&str],
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<String>>();
+
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            let keys: Vec<&str> = keys.iter().map(|k| k.as_str()).collect();
+            {
+                let mut state = self.state();
+                for key in &keys {
+                    state.pending.remove(*key);
+                }
+            }
+
+            self.l2.remove_many(&keys).await?;
+            self.invalidate(&keys).await
+        })
+    }
+
+    fn purge_expired(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>
+    {
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+            self.flush_pending().await?;
+
+            self.l1.purge_expired().await?;
+            self.l2.purge_expired().await
+        })
+    }
+
+    fn clear(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            *self.state() = TierState::default();
+            self.l2.clear().await?;
+            self.l1.clear().await
+        })
+    }
+
+    fn flush(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        Box::pin(async move {
+            let _lock = self.lock.lock().await;
+
+            self.flush_pending().await?;
+            self.l2.flush().await
+        })
+    }
+}
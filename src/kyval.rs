@@ -13,17 +13,33 @@
  * Credits to Alexandru Bereghici: https://github.com/chrisllontop/keyv-rust
  */
 
+use futures::stream::{self, Stream, TryStreamExt};
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value;
-use std::{path::Path, sync::Arc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
 
+#[cfg(feature = "libsql")]
 use crate::adapter::KyvalStoreBuilder;
-use crate::{Store, StoreError, StoreModel};
+use crate::{
+    EntryMeta, ScanOptions, ScanPage, Store, StoreError, StoreModel,
+    StoreTransaction, Ttl,
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum KyvalError {
     #[error("Store error: {0}")]
     StoreError(#[from] StoreError),
+
+    #[error("Failed to decode the value of key `{key}`")]
+    DecodeError {
+        key: String,
+        #[source]
+        source: serde_json::Error,
+    },
 }
 
 /// Key-Value Store Interface
@@ -41,16 +57,21 @@ pub enum KyvalError {
 ///
 /// ```
 /// # use kyval::Kyval;
-/// let kyval = Kyval::default();
+/// # use kyval::adapter::MemoryStore;
+/// #[tokio::main]
+/// async fn main() {
+///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+/// }
 /// ```
 ///
 /// ## Set and get a value
 ///
 /// ```rust,no_run
 /// # use kyval::Kyval;
+/// # use kyval::adapter::MemoryStore;
 /// #[tokio::main]
 /// async fn main() {
-///     let kyval = Kyval::default();
+///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
 ///
 ///     kyval.set("array", vec!["hola", "test"]).await.unwrap();
 ///
@@ -74,6 +95,8 @@ pub enum KyvalError {
 /// ```
 pub struct Kyval {
     store: Arc<dyn Store>,
+    // One lock per key with a `get_or_insert_with` loader in flight.
+    loads: Loads,
 }
 
 impl Kyval {
@@ -94,9 +117,10 @@ impl Kyval {
     ///
     /// ```rust,no_run
     /// # use kyval::{Kyval};
-    /// # use kyval::adapter::KyvalStoreBuilder;
+    /// # #[cfg(feature = "libsql")]
     /// #[tokio::main]
     /// async fn main() {
+    /// # use kyval::adapter::KyvalStoreBuilder;
     /// let store = KyvalStoreBuilder::new()
     ///     .uri(":memory:")
     ///     .table_name("custom_table_name")
@@ -106,6 +130,8 @@ impl Kyval {
     ///
     /// let kyval = Kyval::try_new(store).await.unwrap();
     /// }
+    /// # #[cfg(not(feature = "libsql"))]
+    /// # fn main() {}
     /// ```
     pub async fn try_new<S: Store + 'static>(
         store: S,
@@ -113,9 +139,38 @@ impl Kyval {
         store.initialize().await?;
         Ok(Self {
             store: Arc::new(store),
+            loads: Loads::default(),
         })
     }
 
+    /// Creates a new `Kyval` instance backed by an in-memory `KyvalStore`.
+    ///
+    /// This is useful for quickly setting up a `Kyval` instance without needing to
+    /// configure a specific storage backend. Unlike a blocking constructor, it is
+    /// safe to call from within an async context.
+    ///
+    /// # Errors
+    ///
+    /// Returns `KyvalError` if the store fails to build or initialize.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::in_memory().await.unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "libsql")]
+    pub async fn in_memory() -> Result<Self, KyvalError> {
+        let store = KyvalStoreBuilder::new()
+            .uri(std::path::Path::new(":memory:"))
+            .build()
+            .await?;
+        Self::try_new(store).await
+    }
+
     /// Sets a value for a given key without a TTL.
     ///
     /// # Arguments
@@ -123,6 +178,10 @@ impl Kyval {
     /// * `key` - The key under which the value is stored.
     /// * `value` - The value to store. Must implement `Serialize`.
     ///
+    /// # Returns
+    ///
+    /// Returns the previous entry stored under `key`, or `None` if there was none.
+    ///
     /// # Errors
     ///
     /// Returns `KyvalError` if the operation fails.
@@ -131,9 +190,10 @@ impl Kyval {
     ///
     /// ```rust,no_run
     /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
     /// #[tokio::main]
     /// async fn main() {
-    ///     let kyval = Kyval::default();
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
     ///     kyval.set("key", "hello world").await.unwrap();
     /// }
     /// ```
@@ -155,17 +215,21 @@ impl Kyval {
     /// * `value` - The value to be stored, which must implement `Serialize`.
     /// * `ttl` - The time-to-live (in seconds) for the key-value pair.
     ///
+    /// Once the TTL has elapsed the key is treated as absent by `get` and `list`.
+    ///
     /// # Returns
     ///
-    /// Returns an `Ok` result on successful insertion, or a `KyvalError` on failure.
+    /// Returns the previous entry stored under `key`, or `None` if there was none,
+    /// or a `KyvalError` on failure.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
     /// #[tokio::main]
     /// async fn main() {
-    ///     let kyval = Kyval::default();
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
     ///     kyval.set_with_ttl("temp_key", "temp_value", 3600).await.unwrap(); // Expires in 1 hour
     /// }
     /// ```
@@ -180,6 +244,167 @@ impl Kyval {
         Ok(self.store.set(key, json_value, Some(ttl)).await?)
     }
 
+    /// Sets a value for a given key with an optional TTL (Time-To-Live) and returns
+    /// the entry as it was stored.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key.
+    /// * `value` - The value to be stored, which must implement `Serialize`.
+    /// * `ttl` - The optional time-to-live (in seconds) for the key-value pair.
+    ///
+    /// # Returns
+    ///
+    /// Returns the stored `StoreModel`, including its expiry, or a `KyvalError` on
+    /// failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     let stored = kyval.set_returning("key", "value", Some(60)).await.unwrap();
+    ///     println!("{} expires at {:?}", stored.key, stored.expires_at);
+    /// }
+    /// ```
+    pub async fn set_returning<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        ttl: Option<u64>,
+    ) -> Result<StoreModel, KyvalError> {
+        let json_value = serde_json::to_value(value)
+            .map_err(|e| StoreError::SerializationError { source: e })?;
+        Ok(self.store.set_returning(key, json_value, ttl).await?)
+    }
+
+    /// Sets several values in one atomic operation.
+    ///
+    /// Either every entry is stored or, if any of them fails, none of them are.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The `(key, value, ttl)` triples to store. Values must implement
+    ///   `Serialize` and the optional TTL is in seconds.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `Ok` result if every entry was stored, or a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     let entries = (0..1000).map(|i| (format!("key_{}", i), i, None));
+    ///     kyval.set_many(entries).await.unwrap();
+    /// }
+    /// ```
+    pub async fn set_many<I, K, T>(&self, entries: I) -> Result<(), KyvalError>
+    where
+        I: IntoIterator<Item = (K, T, Option<u64>)>,
+        K: Into<String>,
+        T: Serialize,
+    {
+        let entries = entries
+            .into_iter()
+            .map(|(key, value, ttl)| {
+                let json_value = serde_json::to_value(value).map_err(|e| {
+                    StoreError::SerializationError { source: e }
+                })?;
+                Ok((key.into(), json_value, ttl))
+            })
+            .collect::<Result<Vec<_>, StoreError>>()?;
+        Ok(self.store.set_many(entries).await?)
+    }
+
+    /// Sets a value only if the key does not exist yet, or has expired.
+    ///
+    /// The check and the write happen atomically, which makes this suitable for
+    /// idempotency keys and simple locks.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key.
+    /// * `value` - The value to be stored, which must implement `Serialize`.
+    /// * `ttl` - The optional time-to-live (in seconds) for the key-value pair.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the value was written, `Ok(false)` if the key already
+    /// exists, or a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     assert!(kyval.set_if_absent("request:42", "done", Some(86400)).await.unwrap());
+    ///     assert!(!kyval.set_if_absent("request:42", "done", Some(86400)).await.unwrap());
+    /// }
+    /// ```
+    pub async fn set_if_absent<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        ttl: Option<u64>,
+    ) -> Result<bool, KyvalError> {
+        let json_value = serde_json::to_value(value)
+            .map_err(|e| StoreError::SerializationError { source: e })?;
+        Ok(self.store.set_if_absent(key, json_value, ttl).await?)
+    }
+
+    /// Sets a value only if the key already exists and has not expired.
+    ///
+    /// The check and the write happen atomically.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key.
+    /// * `value` - The value to be stored, which must implement `Serialize`.
+    /// * `ttl` - The optional time-to-live (in seconds) for the key-value pair.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the value was written, `Ok(false)` if the key does not
+    /// exist, or a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     assert!(!kyval.set_if_present("key", "value", None).await.unwrap());
+    ///     kyval.set("key", "old").await.unwrap();
+    ///     assert!(kyval.set_if_present("key", "new", None).await.unwrap());
+    /// }
+    /// ```
+    pub async fn set_if_present<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        ttl: Option<u64>,
+    ) -> Result<bool, KyvalError> {
+        let json_value = serde_json::to_value(value)
+            .map_err(|e| StoreError::SerializationError { source: e })?;
+        Ok(self.store.set_if_present(key, json_value, ttl).await?)
+    }
+
     /// Retrieves a value based on a key.
     ///
     /// # Arguments
@@ -195,9 +420,10 @@ impl Kyval {
     ///
     /// ```rust,no_run
     /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
     /// #[tokio::main]
     /// async fn main() {
-    ///     let kyval = Kyval::default();
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
     ///
     ///     kyval.set("array", vec!["hola", "test"]).await.unwrap();
     ///
@@ -223,123 +449,1124 @@ impl Kyval {
         Ok(self.store.get(key).await?)
     }
 
-    /// Lists all key-value pairs stored in the Kyval store.
+    /// Retrieves a value based on a key, failing if the key is absent.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key to retrieve the value for.
     ///
     /// # Returns
     ///
-    /// Returns a `Result` containing a `Vec` of tuples, where each tuple contains the key (as a `String`) and the corresponding value (as a `Value`). If an error occurs, a `KyvalError` is returned.
+    /// Returns an `Ok` result with the value on success. A key that does not exist
+    /// or has expired yields `StoreError::NotFound`, wrapped in a `KyvalError`.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
     /// #[tokio::main]
     /// async fn main() {
-    ///     let kyval = Kyval::default();
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
     ///
-    ///     let pairs = kyval.list().await.unwrap();
+    ///     kyval.set("string", "life long").await.unwrap();
     ///
-    ///     for item in pairs {
-    ///         println!("Key: {}, Value: {}", item.key, item.value);
-    ///     }
+    ///     let value = kyval.get_required("string").await.unwrap();
+    ///     assert_eq!(value, "life long");
+    ///     assert!(kyval.get_required("missing").await.is_err());
     /// }
     /// ```
-    pub async fn list(&self) -> Result<Vec<StoreModel>, KyvalError> {
-        Ok(self.store.list().await?)
+    pub async fn get_required(&self, key: &str) -> Result<Value, KyvalError> {
+        Ok(self.store.get_required(key).await?)
     }
 
-    /// Removes a specified key from the store.
+    /// Retrieves a value based on a key and deserializes it into `T`.
     ///
     /// # Arguments
     ///
-    /// * `key` - A string slice that represents the key to be removed.
+    /// * `key` - A string slice that holds the key to retrieve the value for.
     ///
     /// # Returns
     ///
-    /// Returns an `Ok` result if the key has been successfully removed, or a `KyvalError`
-    /// on failure.
+    /// Returns an `Ok` result with `Option<T>` on success, where `None` indicates the
+    /// key does not exist, or a `KyvalError` on failure. A value that cannot be
+    /// deserialized into `T` yields `KyvalError::DecodeError` naming the key.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
     /// #[tokio::main]
     /// async fn main() {
-    ///     let kyval = Kyval::default();
-    ///     kyval.remove("my_key").await.unwrap(); // Removes "my_key" from the store
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     kyval.set("array", vec!["hola", "test"]).await.unwrap();
+    ///
+    ///     let array: Option<Vec<String>> = kyval.get_as("array").await.unwrap();
+    ///     assert_eq!(array, Some(vec!["hola".to_string(), "test".to_string()]));
     /// }
     /// ```
-    pub async fn remove(&self, key: &str) -> Result<(), KyvalError> {
-        Ok(self.store.remove(key).await?)
+    pub async fn get_as<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, KyvalError> {
+        match self.store.get(key).await? {
+            Some(value) => Ok(Some(decode(key, value)?)),
+            None => Ok(None),
+        }
     }
 
-    /// Removes multiple keys from the store in one operation.
+    /// Retrieves the value of a key, or computes, stores and returns it if the key
+    /// is absent.
+    ///
+    /// Concurrent calls for the same key through this `Kyval` instance share a
+    /// single call to `loader`: the first caller runs it while the others wait and
+    /// then read the value it stored. If the loader fails, its error is returned to
+    /// that caller only, and the next waiter runs its own loader.
     ///
     /// # Arguments
     ///
-    /// * `keys` - A slice of strings or string-like objects that represent the keys to be removed.
+    /// * `key` - A string slice that holds the key.
+    /// * `ttl` - The optional time-to-live (in seconds) of a computed value.
+    /// * `loader` - Computes the value when the key is absent.
     ///
     /// # Returns
     ///
-    /// Returns an `Ok` result if the keys have been successfully removed, or a `KyvalError`
-    /// on failure.
+    /// Returns the stored or computed value, or a `KyvalError` if the loader fails,
+    /// the stored value cannot be decoded, or the store fails.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
     /// #[tokio::main]
     /// async fn main() {
-    ///     let kyval = Kyval::default();
-    ///     kyval.remove_many(&["key1", "key2"]).await.unwrap(); // Removes "key1" and "key2"
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     let rate: f64 = kyval
+    ///         .get_or_insert_with("rate:usd:idr", Some(300), || async {
+    ///             // Fetch the rate from a slow upstream service.
+    ///             Ok(15_500.0)
+    ///         })
+    ///         .await
+    ///         .unwrap();
     /// }
     /// ```
-    pub async fn remove_many<T: AsRef<str> + Sync>(
+    pub async fn get_or_insert_with<T, F, Fut>(
+        &self,
+        key: &str,
+        ttl: Option<u64>,
+        loader: F,
+    ) -> Result<T, KyvalError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, KyvalError>>,
+    {
+        if let Some(value) = self.get_as(key).await? {
+            return Ok(value);
+        }
+
+        let load = Load::new(&self.loads, key);
+        let _held = load.lock.clone().lock_owned().await;
+
+        // The caller that held the lock before this one may have stored it.
+        if let Some(value) = self.get_as(key).await? {
+            return Ok(value);
+        }
+
+        let value = loader().await?;
+        let json_value = serde_json::to_value(&value)
+            .map_err(|e| StoreError::SerializationError { source: e })?;
+        self.store.set(key, json_value, ttl).await?;
+
+        Ok(value)
+    }
+
+    /// Retrieves the values of several keys in one operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - A slice of strings or string-like objects that represent the keys to retrieve.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `HashMap` with an entry for every key that exists; missing and expired
+    /// keys are left out. Returns a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     kyval.set("flag_a", true).await.unwrap();
+    ///     kyval.set("flag_b", false).await.unwrap();
+    ///
+    ///     let flags = kyval.get_many(&["flag_a", "flag_b", "flag_c"]).await.unwrap();
+    ///     assert_eq!(flags.len(), 2);
+    /// }
+    /// ```
+    pub async fn get_many<T: AsRef<str> + Sync>(
         &self,
         keys: &[T],
-    ) -> Result<(), KyvalError> {
+    ) -> Result<HashMap<String, Value>, KyvalError> {
         let keys: Vec<&str> = keys.iter().map(|k| k.as_ref()).collect();
-        Ok(self.store.remove_many(&keys).await?)
+        Ok(self.store.get_many(&keys).await?)
     }
 
-    /// Clears the entire store, removing all key-value pairs.
+    /// Checks whether a key exists, without fetching or decoding its value.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key to check.
     ///
     /// # Returns
     ///
-    /// Returns an `Ok` result if the store has been successfully cleared, or a `KyvalError`
-    /// on failure.
+    /// Returns `true` if the key exists and has not expired, or a `KyvalError` on
+    /// failure.
     ///
     /// # Examples
     ///
     /// ```rust,no_run
     /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
     /// #[tokio::main]
     /// async fn main() {
-    ///     let kyval = Kyval::default();
-    ///     kyval.clear().await.unwrap(); // Clears the entire store
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     kyval.set("session", "abc").await.unwrap();
+    ///     assert!(kyval.exists("session").await.unwrap());
+    ///     assert!(!kyval.exists("missing").await.unwrap());
     /// }
     /// ```
-    pub async fn clear(&self) -> Result<(), KyvalError> {
-        Ok(self.store.clear().await?)
+    pub async fn exists(&self, key: &str) -> Result<bool, KyvalError> {
+        Ok(self.store.exists(key).await?)
     }
-}
 
-/// Provides a default implementation for the `Kyval` struct, which creates an in-memory store.
-/// This is useful for quickly setting up a `Kyval` instance without needing to configure a
-/// specific storage backend.
-impl Default for Kyval {
-    fn default() -> Self {
-        let runtime = tokio::runtime::Runtime::new()
-            .expect("Failed to create async runtime");
-        let store = runtime.block_on(async {
-            KyvalStoreBuilder::new()
-                .uri(Path::new(":memory:"))
-                .build()
-                .await
-                .expect("Failed to build KyvalStore")
-        });
-        Self {
-            store: Arc::new(store),
-        }
+    /// Retrieves the bookkeeping kept for a key, without fetching or decoding its
+    /// value.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key to inspect.
+    ///
+    /// # Returns
+    ///
+    /// Returns the key's `EntryMeta` (creation, update and expiry times, size and
+    /// version), `None` if the key is missing or expired, or a `KyvalError` on
+    /// failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     kyval.set("report", "quarterly numbers").await.unwrap();
+    ///
+    ///     let meta = kyval.metadata("report").await.unwrap().unwrap();
+    ///     println!("{} bytes, updated at {:?}", meta.size, meta.updated_at);
+    /// }
+    /// ```
+    pub async fn metadata(
+        &self,
+        key: &str,
+    ) -> Result<Option<EntryMeta>, KyvalError> {
+        Ok(self.store.metadata(key).await?)
+    }
+
+    /// Retrieves a value along with its version, for use with `set_if_version`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key to retrieve the value for.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(Some((value, version)))` if the key exists, `Ok(None)` if it does
+    /// not exist or has expired, or a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     kyval.set("key", "value").await.unwrap();
+    ///
+    ///     let (value, version) = kyval.get_with_version("key").await.unwrap().unwrap();
+    ///     assert_eq!(version, 1);
+    /// }
+    /// ```
+    pub async fn get_with_version(
+        &self,
+        key: &str,
+    ) -> Result<Option<(Value, u64)>, KyvalError> {
+        Ok(self.store.get_with_version(key).await?)
     }
+
+    /// Sets a value only if the key still has the expected version, giving optimistic
+    /// concurrency without external locks.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key.
+    /// * `value` - The value to be stored, which must implement `Serialize`.
+    /// * `ttl` - The optional time-to-live (in seconds) for the key-value pair.
+    /// * `expected_version` - The version read with `get_with_version`, or 0 if the
+    ///   key must not exist yet.
+    ///
+    /// # Returns
+    ///
+    /// Returns the new version on success. If another writer got there first, returns
+    /// `StoreError::VersionConflict` wrapped in a `KyvalError`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::{Kyval, KyvalError, StoreError};
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     kyval.set("counter", 0).await.unwrap();
+    ///
+    ///     loop {
+    ///         let (value, version) = kyval.get_with_version("counter").await.unwrap().unwrap();
+    ///         let next = value.as_i64().unwrap() + 1;
+    ///
+    ///         match kyval.set_if_version("counter", next, None, version).await {
+    ///             Ok(_) => break,
+    ///             Err(KyvalError::StoreError(StoreError::VersionConflict { .. })) => continue,
+    ///             Err(e) => panic!("{}", e),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub async fn set_if_version<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        ttl: Option<u64>,
+        expected_version: u64,
+    ) -> Result<u64, KyvalError> {
+        let json_value = serde_json::to_value(value)
+            .map_err(|e| StoreError::SerializationError { source: e })?;
+        Ok(self
+            .store
+            .set_if_version(key, json_value, ttl, expected_version)
+            .await?)
+    }
+
+    /// Atomically increments the integer stored under a key by one.
+    ///
+    /// A missing key is created holding 1.
+    ///
+    /// # Returns
+    ///
+    /// Returns the value after the increment, or a `KyvalError` on failure, such as
+    /// `StoreError::NotNumeric` when the existing value is not an integer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     assert_eq!(kyval.incr("hits").await.unwrap(), 1);
+    ///     assert_eq!(kyval.incr("hits").await.unwrap(), 2);
+    /// }
+    /// ```
+    pub async fn incr(&self, key: &str) -> Result<i64, KyvalError> {
+        self.incr_by(key, 1).await
+    }
+
+    /// Atomically decrements the integer stored under a key by one.
+    ///
+    /// A missing key is created holding -1.
+    ///
+    /// # Returns
+    ///
+    /// Returns the value after the decrement, or a `KyvalError` on failure, such as
+    /// `StoreError::NotNumeric` when the existing value is not an integer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     kyval.set("stock", 10).await.unwrap();
+    ///     assert_eq!(kyval.decr("stock").await.unwrap(), 9);
+    /// }
+    /// ```
+    pub async fn decr(&self, key: &str) -> Result<i64, KyvalError> {
+        self.incr_by(key, -1).await
+    }
+
+    /// Atomically adds `delta` to the integer stored under a key.
+    ///
+    /// A missing or expired key is created holding `delta`; an existing key keeps its
+    /// TTL.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key.
+    /// * `delta` - The amount to add, which may be negative.
+    ///
+    /// # Returns
+    ///
+    /// Returns the value after the increment, or a `KyvalError` on failure, such as
+    /// `StoreError::NotNumeric` when the existing value is not an integer or
+    /// `StoreError::Overflow` when the result does not fit in an `i64`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     assert_eq!(kyval.incr_by("sequence", 100).await.unwrap(), 100);
+    /// }
+    /// ```
+    pub async fn incr_by(
+        &self,
+        key: &str,
+        delta: i64,
+    ) -> Result<i64, KyvalError> {
+        Ok(self.store.incr_by(key, delta).await?)
+    }
+
+    /// Atomically adds `delta` to the number stored under a key.
+    ///
+    /// A missing or expired key is created holding `delta`; an existing key keeps its
+    /// TTL.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key.
+    /// * `delta` - The amount to add, which may be negative.
+    ///
+    /// # Returns
+    ///
+    /// Returns the value after the increment, or a `KyvalError` on failure, such as
+    /// `StoreError::NotNumeric` when the existing value is not a number.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     assert_eq!(kyval.incr_by_float("balance", 2.5).await.unwrap(), 2.5);
+    /// }
+    /// ```
+    pub async fn incr_by_float(
+        &self,
+        key: &str,
+        delta: f64,
+    ) -> Result<f64, KyvalError> {
+        Ok(self.store.incr_by_float(key, delta).await?)
+    }
+
+    /// Returns the remaining lifetime of a key.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key to inspect.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(Some(Ttl))` if the key exists, `Ok(None)` if it does not exist or
+    /// has expired, or a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::{Kyval, Ttl};
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     kyval.set_with_ttl("session", "data", 3600).await.unwrap();
+    ///
+    ///     match kyval.ttl("session").await.unwrap() {
+    ///         Some(Ttl::Expires(remaining)) => println!("Expires in {:?}", remaining),
+    ///         Some(Ttl::Persistent) => println!("Never expires"),
+    ///         None => println!("Missing"),
+    ///     }
+    /// }
+    /// ```
+    pub async fn ttl(&self, key: &str) -> Result<Option<Ttl>, KyvalError> {
+        Ok(self.store.ttl(key).await?)
+    }
+
+    /// Sets an expiry TTL (Time-To-Live) on an existing key without rewriting its value.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key.
+    /// * `ttl` - The time-to-live (in seconds), counted from now.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the key exists, `Ok(false)` if it does not exist or has
+    /// expired, or a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     kyval.set("key", "value").await.unwrap();
+    ///     kyval.expire("key", 60).await.unwrap(); // Expires in 1 minute
+    /// }
+    /// ```
+    pub async fn expire(
+        &self,
+        key: &str,
+        ttl: u64,
+    ) -> Result<bool, KyvalError> {
+        Ok(self.store.expire(key, ttl).await?)
+    }
+
+    /// Removes the expiry from an existing key, so it never expires.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the key exists, `Ok(false)` if it does not exist or has
+    /// expired, or a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     kyval.set_with_ttl("key", "value", 60).await.unwrap();
+    ///     kyval.persist("key").await.unwrap(); // Never expires
+    /// }
+    /// ```
+    pub async fn persist(&self, key: &str) -> Result<bool, KyvalError> {
+        Ok(self.store.persist(key).await?)
+    }
+
+    /// Restarts the expiry of an existing key from now, using the TTL it was last
+    /// given. Useful for sliding sessions.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that holds the key.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the key exists, `Ok(false)` if it does not exist or has
+    /// expired, or a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     kyval.set_with_ttl("session", "data", 1800).await.unwrap();
+    ///     kyval.touch("session").await.unwrap(); // Expires 30 minutes from now again
+    /// }
+    /// ```
+    pub async fn touch(&self, key: &str) -> Result<bool, KyvalError> {
+        Ok(self.store.touch(key).await?)
+    }
+
+    /// Lists all key-value pairs stored in the Kyval store.
+    ///
+    /// Every entry is loaded into memory at once; use `scan` to page through
+    /// large stores.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing a `Vec` of tuples, where each tuple contains the key (as a `String`) and the corresponding value (as a `Value`). If an error occurs, a `KyvalError` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     let pairs = kyval.list().await.unwrap();
+    ///
+    ///     for item in pairs {
+    ///         println!("Key: {}, Value: {}", item.key, item.value);
+    ///     }
+    /// }
+    /// ```
+    pub async fn list(&self) -> Result<Vec<StoreModel>, KyvalError> {
+        Ok(self.store.list().await?)
+    }
+
+    /// Fetches one page of the entries selected by `options`, in key order.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The prefix, range, page size, direction and cursor to use.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `ScanPage` with the entries and, when more remain, a cursor to
+    /// pass to `ScanOptions::cursor` for the next page. A cursor this store did
+    /// not produce yields `StoreError::InvalidCursor`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::{Kyval, ScanOptions};
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     let mut options = ScanOptions::new().prefix("user:").limit(100);
+    ///     loop {
+    ///         let page = kyval.scan(options.clone()).await.unwrap();
+    ///         for item in &page.items {
+    ///             println!("Key: {}, Value: {}", item.key, item.value);
+    ///         }
+    ///         match page.cursor {
+    ///             Some(cursor) => options = options.cursor(cursor),
+    ///             None => break,
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub async fn scan(
+        &self,
+        options: ScanOptions,
+    ) -> Result<ScanPage, KyvalError> {
+        Ok(self.store.scan(options).await?)
+    }
+
+    /// Streams every unexpired entry whose key starts with `prefix`, in key
+    /// order.
+    ///
+    /// Entries are fetched lazily, one `scan` page at a time, so memory use
+    /// stays bounded however many entries are walked. Each page is read
+    /// independently: entries written while the stream is being consumed may or
+    /// may not be observed, depending on where they sort relative to the
+    /// current position.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The key prefix to match. Pass `""` to stream the whole store.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// use futures::TryStreamExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     let mut entries = kyval.stream("user:");
+    ///     while let Some(item) = entries.try_next().await.unwrap() {
+    ///         println!("Key: {}, Value: {}", item.key, item.value);
+    ///     }
+    /// }
+    /// ```
+    pub fn stream(
+        &self,
+        prefix: &str,
+    ) -> Pin<Box<dyn Stream<Item = Result<StoreModel, KyvalError>> + Send>>
+    {
+        let store = self.store.clone();
+        let options = Some(ScanOptions::new().prefix(prefix));
+
+        let entries = stream::try_unfold(options, move |options| {
+            let store = store.clone();
+            async move {
+                let Some(options) = options else {
+                    return Ok(None);
+                };
+
+                let page = store.scan(options.clone()).await?;
+                let next = page.cursor.map(|cursor| options.cursor(cursor));
+
+                Ok::<_, KyvalError>(Some((page.items, next)))
+            }
+        })
+        .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+        .try_flatten();
+
+        Box::pin(entries)
+    }
+
+    /// Counts the keys starting with `prefix`, without fetching their values.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The key prefix to match. Pass `""` to count every key.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of unexpired matching keys, or a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     kyval.set("user:1", "alice").await.unwrap();
+    ///     kyval.set("user:2", "bob").await.unwrap();
+    ///     assert_eq!(kyval.count("user:").await.unwrap(), 2);
+    /// }
+    /// ```
+    pub async fn count(&self, prefix: &str) -> Result<u64, KyvalError> {
+        Ok(self.store.count(prefix).await?)
+    }
+
+    /// Lists the keys starting with `prefix`, without fetching their values.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The key prefix to match. Pass `""` to list every key.
+    ///
+    /// # Returns
+    ///
+    /// Returns the unexpired matching keys in key order, or a `KyvalError` on
+    /// failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     kyval.set("user:1", "alice").await.unwrap();
+    ///     kyval.set("user:2", "bob").await.unwrap();
+    ///     assert_eq!(kyval.keys("user:").await.unwrap(), vec!["user:1", "user:2"]);
+    /// }
+    /// ```
+    pub async fn keys(&self, prefix: &str) -> Result<Vec<String>, KyvalError> {
+        Ok(self.store.keys(prefix).await?)
+    }
+
+    /// Lists all key-value pairs stored in the Kyval store, deserializing every
+    /// value into `T`.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Vec` of `(key, value)` tuples ordered by key, or a `KyvalError` on
+    /// failure. The first value that cannot be deserialized into `T` yields
+    /// `KyvalError::DecodeError` naming its key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///
+    ///     kyval.set("a", 1).await.unwrap();
+    ///     kyval.set("b", 2).await.unwrap();
+    ///
+    ///     let pairs: Vec<(String, i32)> = kyval.list_as().await.unwrap();
+    ///     assert_eq!(pairs, vec![("a".to_string(), 1), ("b".to_string(), 2)]);
+    /// }
+    /// ```
+    pub async fn list_as<T: DeserializeOwned>(
+        &self,
+    ) -> Result<Vec<(String, T)>, KyvalError> {
+        self.store
+            .list()
+            .await?
+            .into_iter()
+            .map(|item| {
+                let value = decode(&item.key, item.value)?;
+                Ok((item.key, value))
+            })
+            .collect()
+    }
+
+    /// Removes a specified key from the store.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A string slice that represents the key to be removed.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `Ok` result if the key has been successfully removed, or a `KyvalError`
+    /// on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     kyval.remove("my_key").await.unwrap(); // Removes "my_key" from the store
+    /// }
+    /// ```
+    pub async fn remove(&self, key: &str) -> Result<(), KyvalError> {
+        Ok(self.store.remove(key).await?)
+    }
+
+    /// Removes multiple keys from the store in one operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - A slice of strings or string-like objects that represent the keys to be removed.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `Ok` result if the keys have been successfully removed, or a `KyvalError`
+    /// on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     kyval.remove_many(&["key1", "key2"]).await.unwrap(); // Removes "key1" and "key2"
+    /// }
+    /// ```
+    pub async fn remove_many<T: AsRef<str> + Sync>(
+        &self,
+        keys: &[T],
+    ) -> Result<(), KyvalError> {
+        let keys: Vec<&str> = keys.iter().map(|k| k.as_ref()).collect();
+        Ok(self.store.remove_many(&keys).await?)
+    }
+
+    /// Deletes every expired key from the store.
+    ///
+    /// Expired keys are already hidden from reads; this reclaims the space they
+    /// still occupy. It is meant for cron-style maintenance when the store's
+    /// background sweeper is not enabled.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of keys that were deleted, or a `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     let removed = kyval.purge_expired().await.unwrap();
+    ///     println!("Purged {} expired keys", removed);
+    /// }
+    /// ```
+    pub async fn purge_expired(&self) -> Result<u64, KyvalError> {
+        Ok(self.store.purge_expired().await?)
+    }
+
+    /// Runs several operations atomically in one transaction.
+    ///
+    /// The closure receives a `KyvalTransaction` through which it can `get`, `set`
+    /// and `remove` keys. When the closure returns `Ok`, the transaction is committed;
+    /// when it returns `Err`, every change made through the transaction is rolled back
+    /// and the error is returned.
+    ///
+    /// The closure borrows the `KyvalTransaction` and returns a boxed future, so the
+    /// transaction cannot outlive it. The store may be locked for the duration of the
+    /// transaction, so the closure should only use the `KyvalTransaction`, not this
+    /// `Kyval` instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StoreError::Unsupported` if the underlying store does not implement
+    /// `TransactionalStore`, the closure's error if it fails, or a `KyvalError` if the
+    /// transaction cannot be started or committed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # #[cfg(feature = "libsql")]
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::in_memory().await.unwrap();
+    ///     kyval.set("from", 100).await.unwrap();
+    ///
+    ///     // Move a value between two keys atomically.
+    ///     kyval
+    ///         .transaction(|tx| {
+    ///             Box::pin(async move {
+    ///                 if let Some(value) = tx.get("from").await? {
+    ///                     tx.set("to", value).await?;
+    ///                     tx.remove("from").await?;
+    ///                 }
+    ///                 Ok(())
+    ///             })
+    ///         })
+    ///         .await
+    ///         .unwrap();
+    /// }
+    /// # #[cfg(not(feature = "libsql"))]
+    /// # fn main() {}
+    /// ```
+    pub async fn transaction<F, T>(&self, f: F) -> Result<T, KyvalError>
+    where
+        F: for<'t> FnOnce(&'t KyvalTransaction) -> TransactionFuture<'t, T>,
+    {
+        let store = self.store.as_transactional().ok_or_else(|| {
+            StoreError::Unsupported("transactions".to_string())
+        })?;
+        let tx = KyvalTransaction {
+            inner: store.begin().await?,
+        };
+
+        match f(&tx).await {
+            Ok(result) => {
+                tx.inner.commit().await?;
+                Ok(result)
+            }
+            Err(e) => {
+                if let Err(rollback_error) = tx.inner.rollback().await {
+                    log::warn!(
+                        "Failed to roll back the transaction: {}",
+                        rollback_error
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Clears the entire store, removing all key-value pairs.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `Ok` result if the store has been successfully cleared, or a `KyvalError`
+    /// on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::MemoryStore;
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+    ///     kyval.clear().await.unwrap(); // Clears the entire store
+    /// }
+    /// ```
+    pub async fn clear(&self) -> Result<(), KyvalError> {
+        Ok(self.store.clear().await?)
+    }
+
+    /// Writes any writes the store has buffered, such as those held by a
+    /// `TieredStore` with `WritePolicy::WriteBack`, to the underlying storage.
+    ///
+    /// Call it before dropping the instance, or buffered writes are lost. For
+    /// stores that do not buffer writes it does nothing.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `Ok` result once every buffered write has been stored, or a
+    /// `KyvalError` on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kyval::Kyval;
+    /// # use kyval::adapter::{MemoryStore, TieredStore, WritePolicy};
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let store = TieredStore::new(MemoryStore::new(), MemoryStore::new())
+    ///         .write_policy(WritePolicy::WriteBack);
+    ///     let kyval = Kyval::try_new(store).await.unwrap();
+    ///
+    ///     kyval.set("key", "value").await.unwrap();
+    ///     kyval.flush().await.unwrap();
+    /// }
+    /// ```
+    pub async fn flush(&self) -> Result<(), KyvalError> {
+        Ok(self.store.flush().await?)
+    }
+}
+
+/// The future returned by a `Kyval::transaction` closure, borrowing the
+/// transaction for its lifetime `'t`.
+pub type TransactionFuture<'t, T> =
+    Pin<Box<dyn Future<Output = Result<T, KyvalError>> + Send + 't>>;
+
+/// A handle to a transaction started by `Kyval::transaction`.
+///
+/// Operations made through it see each other's effects, and are committed or
+/// rolled back together when the transaction ends. It is only lent to the
+/// closure, so it cannot be kept once the transaction is over:
+///
+/// ```compile_fail
+/// # use kyval::Kyval;
+/// # use kyval::adapter::MemoryStore;
+/// #[tokio::main]
+/// async fn main() {
+///     let kyval = Kyval::try_new(MemoryStore::new()).await.unwrap();
+///
+///     kyval
+///         .transaction(|tx| {
+///             Box::pin(async move {
+///                 tokio::spawn(async move { tx.set("key", 1).await });
+///                 Ok(())
+///             })
+///         })
+///         .await
+///         .unwrap();
+/// }
+/// ```
+pub struct KyvalTransaction {
+    inner: Box<dyn StoreTransaction>,
+}
+
+impl KyvalTransaction {
+    /// Retrieves a value based on a key within the transaction.
+    ///
+    /// Returns `Ok(None)` if the key does not exist or has expired.
+    pub async fn get(&self, key: &str) -> Result<Option<Value>, KyvalError> {
+        Ok(self.inner.get(key).await?)
+    }
+
+    /// Retrieves a value based on a key within the transaction and deserializes
+    /// it into `T`.
+    ///
+    /// Returns `Ok(None)` if the key does not exist or has expired, and
+    /// `KyvalError::DecodeError` if the value cannot be deserialized into `T`.
+    pub async fn get_as<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, KyvalError> {
+        match self.inner.get(key).await? {
+            Some(value) => Ok(Some(decode(key, value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Sets a value for a given key within the transaction, without a TTL.
+    pub async fn set<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+    ) -> Result<(), KyvalError> {
+        let json_value = serde_json::to_value(value)
+            .map_err(|e| StoreError::SerializationError { source: e })?;
+        Ok(self.inner.set(key, json_value, None).await?)
+    }
+
+    /// Sets a value for a given key within the transaction, with an expiry TTL
+    /// (Time-To-Live) in seconds.
+    pub async fn set_with_ttl<T: Serialize>(
+        &self,
+        key: &str,
+        value: T,
+        ttl: u64,
+    ) -> Result<(), KyvalError> {
+        let json_value = serde_json::to_value(value)
+            .map_err(|e| StoreError::SerializationError { source: e })?;
+        Ok(self.inner.set(key, json_value, Some(ttl)).await?)
+    }
+
+    /// Removes a specified key within the transaction.
+    pub async fn remove(&self, key: &str) -> Result<(), KyvalError> {
+        Ok(self.inner.remove(key).await?)
+    }
+}
+
+/// Per-key locks of the `get_or_insert_with` calls in flight.
+type Loads = Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>;
+
+/// A claim on the per-key lock of a `get_or_insert_with` call. The lock is
+/// dropped from the map once no other call is using it.
+struct Load<'a> {
+    loads: &'a Loads,
+    key: &'a str,
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl<'a> Load<'a> {
+    fn new(loads: &'a Loads, key: &'a str) -> Self {
+        let lock = loads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+
+        Self { loads, key, lock }
+    }
+}
+
+impl Drop for Load<'_> {
+    fn drop(&mut self) {
+        let mut loads =
+            self.loads.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // Only the map and this claim hold the lock: nobody else is waiting.
+        if Arc::strong_count(&self.lock) == 2 {
+            loads.remove(self.key);
+        }
+    }
+}
+
+/// Deserializes the value stored under `key`, naming the key on failure.
+fn decode<T: DeserializeOwned>(
+    key: &str,
+    value: Value,
+) -> Result<T, KyvalError> {
+    serde_json::from_value(value).map_err(|e| KyvalError::DecodeError {
+        key: key.to_string(),
+        source: e,
+    })
 }
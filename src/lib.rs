@@ -15,3 +15,6 @@ mod store;
 pub use store::*;
 
 pub mod adapter;
+
+#[cfg(feature = "testing")]
+pub mod testing;
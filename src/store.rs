@@ -16,16 +16,232 @@
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoreModel {
     pub key: String,
     pub value: Value,
+    /// When the entry expires, in UTC epoch milliseconds, if it has a TTL.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+/// Bookkeeping about a stored entry, as reported by `Store::metadata`.
+///
+/// Timestamps are UTC epoch milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryMeta {
+    pub key: String,
+    /// When the entry was created. `None` for entries written before creation
+    /// times were recorded.
+    pub created_at: Option<u64>,
+    /// When the entry was last written.
+    pub updated_at: Option<u64>,
+    /// When the entry expires, if it has a TTL.
+    pub expires_at: Option<u64>,
+    /// Size of the stored value in bytes, as encoded by the store.
+    pub size: u64,
+    /// The entry's version, as used by `Store::set_if_version`.
+    pub version: u64,
+}
+
+/// Default number of entries per page returned by `Store::scan`.
+pub const DEFAULT_SCAN_LIMIT: usize = 100;
+
+/// Selects the keys walked by `Store::scan`.
+///
+/// All bounds combine: a key is returned only if it matches the prefix and
+/// falls within the range. Keys are compared byte-wise.
+///
+/// # Examples
+///
+/// ```
+/// # use kyval::ScanOptions;
+/// let options = ScanOptions::new()
+///     .prefix("user:")
+///     .limit(50)
+///     .reverse(true);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanOptions {
+    /// Only return keys starting with this prefix.
+    pub prefix: Option<String>,
+    /// Only return keys greater than or equal to this key.
+    pub start: Option<String>,
+    /// Only return keys strictly less than this key.
+    pub end: Option<String>,
+    /// Maximum number of entries in a page.
+    pub limit: usize,
+    /// Continue after the page that returned this cursor.
+    pub cursor: Option<String>,
+    /// Walk keys in descending instead of ascending order.
+    pub reverse: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScanOptions {
+    pub fn new() -> Self {
+        Self {
+            prefix: None,
+            start: None,
+            end: None,
+            limit: DEFAULT_SCAN_LIMIT,
+            cursor: None,
+            reverse: false,
+        }
+    }
+
+    /// Only returns keys starting with `prefix`.
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Only returns keys in `start..end`, inclusive of `start` and exclusive
+    /// of `end`.
+    pub fn range<S: Into<String>>(mut self, start: S, end: S) -> Self {
+        self.start = Some(start.into());
+        self.end = Some(end.into());
+        self
+    }
+
+    /// Sets the maximum number of entries in a page. Values below 1 are
+    /// treated as 1.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Continues a scan from the cursor returned with the previous page.
+    pub fn cursor<S: Into<String>>(mut self, cursor: S) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Walks keys in descending order when `reverse` is `true`.
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+}
+
+/// A page of entries returned by `Store::scan`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanPage {
+    /// The entries in this page, in scan order.
+    pub items: Vec<StoreModel>,
+    /// An opaque cursor to pass to `ScanOptions::cursor` to fetch the next
+    /// page, or `None` when there are no more entries.
+    pub cursor: Option<String>,
+}
+
+/// Encodes the last key of a page as an opaque scan cursor.
+pub(crate) fn encode_cursor(key: &str) -> String {
+    key.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decodes a cursor produced by `encode_cursor` back into a key.
+pub(crate) fn decode_cursor(cursor: &str) -> Result<String, StoreError> {
+    let invalid = || StoreError::InvalidCursor(cursor.to_string());
+
+    if cursor.len() % 2 != 0 {
+        return Err(invalid());
+    }
+
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| {
+            cursor
+                .get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Returns the smallest string greater than every string starting with
+/// `prefix`, or `None` if no such string exists.
+pub(crate) fn prefix_upper_bound(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+
+    while let Some(last) = chars.pop() {
+        let next =
+            (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32);
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+
+    None
+}
+
+/// Returns the current time in UTC epoch milliseconds.
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Converts a TTL in seconds into milliseconds, clamped so that adding it to
+/// the current epoch time can never overflow 64-bit integers.
+pub(crate) fn ttl_millis(ttl: u64) -> i64 {
+    ttl.saturating_mul(1000).min(i64::MAX as u64 / 2) as i64
+}
+
+/// Explains why an increment of `key` was rejected, given its current value
+/// and whether the increment required an integer.
+pub(crate) fn increment_error(
+    key: String,
+    current: Option<(Value, u64)>,
+    integer: bool,
+) -> StoreError {
+    let numeric = match current {
+        Some((Value::Number(n), _)) => !integer || n.is_i64(),
+        _ => false,
+    };
+
+    if numeric {
+        StoreError::Overflow(key)
+    } else {
+        StoreError::NotNumeric {
+            key,
+            expected: if integer { "an integer" } else { "a number" }
+                .to_string(),
+        }
+    }
+}
+
+/// Remaining lifetime of a key, as reported by `Store::ttl`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ttl {
+    /// The key has no expiry.
+    Persistent,
+    /// The key expires once the duration has elapsed.
+    Expires(Duration),
 }
 
 pub trait Store: Send + Sync {
+    /// Returns this store as a `TransactionalStore` if it supports
+    /// multi-operation transactions.
+    ///
+    /// The default implementation returns `None`; backends that implement
+    /// `TransactionalStore` override it to return `Some(self)`.
+    fn as_transactional(&self) -> Option<&dyn TransactionalStore> {
+        None
+    }
+
     /// Initializes the storage backend.
     /// This method should perform any necessary setup for the storage backend, such as
     /// establishing database connections or ensuring the existence of required files or schemas.
@@ -44,7 +260,7 @@ pub trait Store: Send + Sync {
     ///
     /// # Returns
     /// - `Ok(Some(Value))` if the key exists and the value is successfully retrieved.
-    /// - `Ok(None)` if the key does not exist.
+    /// - `Ok(None)` if the key does not exist or has expired.
     /// - `Err(StoreError)` if there is an error retrieving the value.
     fn get(
         &self,
@@ -53,10 +269,91 @@ pub trait Store: Send + Sync {
         Box<dyn Future<Output = Result<Option<Value>, StoreError>> + Send + '_>,
     >;
 
+    /// Retrieves a value associated with a given key, treating a missing key as
+    /// an error.
+    ///
+    /// # Arguments
+    /// - `key`: A string slice that holds the key for the value to be retrieved.
+    ///
+    /// # Returns
+    /// - `Ok(Value)` if the key exists and the value is successfully retrieved.
+    /// - `Err(StoreError::NotFound)` if the key does not exist or has expired.
+    /// - `Err(StoreError)` if there is an error retrieving the value.
+    fn get_required(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<Value, StoreError>> + Send + '_>>
+    {
+        let key = key.to_string();
+        Box::pin(async move {
+            match self.get(&key).await? {
+                Some(value) => Ok(value),
+                None => Err(StoreError::NotFound(key)),
+            }
+        })
+    }
+
+    /// Retrieves the values associated with several keys in one operation.
+    ///
+    /// # Arguments
+    /// - `keys`: A slice of string slices representing the keys to retrieve.
+    ///
+    /// # Returns
+    /// - `Ok(HashMap<String, Value>)` with an entry for every key that exists.
+    ///   Missing and expired keys are left out.
+    /// - `Err(StoreError)` if there is an error retrieving the values.
+    #[allow(clippy::type_complexity)]
+    fn get_many(
+        &self,
+        keys: &[&str],
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<HashMap<String, Value>, StoreError>>
+                + Send
+                + '_,
+        >,
+    >;
+
+    /// Checks whether a key exists without reading its value.
+    ///
+    /// # Arguments
+    /// - `key`: A string slice representing the key to check.
+    ///
+    /// # Returns
+    /// - `Ok(true)` if the key exists and has not expired, `Ok(false)` otherwise.
+    /// - `Err(StoreError)` if there is an error checking the key.
+    fn exists(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>;
+
+    /// Retrieves the bookkeeping kept for a key, without reading its value.
+    ///
+    /// # Arguments
+    /// - `key`: A string slice representing the key to inspect.
+    ///
+    /// # Returns
+    /// - `Ok(Some(EntryMeta))` if the key exists and has not expired.
+    /// - `Ok(None)` if the key is missing or expired.
+    /// - `Err(StoreError)` if there is an error reading the metadata.
+    #[allow(clippy::type_complexity)]
+    fn metadata(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<EntryMeta>, StoreError>>
+                + Send
+                + '_,
+        >,
+    >;
+
     /// Lists all key-value pairs stored in the store.
     ///
+    /// Every entry is loaded at once; prefer `scan` for large stores.
+    ///
     /// # Returns
-    /// - `Ok(Vec<StoreModel>)` containing all the key-value pairs in the store.
+    /// - `Ok(Vec<StoreModel>)` containing all the unexpired key-value pairs in the store.
     /// - `Err(StoreError)` if there is an error listing the key-value pairs.
     fn list(
         &self,
@@ -68,6 +365,53 @@ pub trait Store: Send + Sync {
         >,
     >;
 
+    /// Walks the keys selected by `options` one page at a time, in key order.
+    ///
+    /// Pages are delimited by key rather than by offset, so a scan stays cheap
+    /// however deep it goes, and keys written between pages are neither
+    /// skipped nor repeated unless they fall behind the cursor.
+    ///
+    /// # Arguments
+    /// - `options`: The prefix, range, page size, direction and cursor to use.
+    ///
+    /// # Returns
+    /// - `Ok(ScanPage)` with up to `options.limit` unexpired entries and the cursor
+    ///   for the next page.
+    /// - `Err(StoreError::InvalidCursor)` if the cursor was not produced by this store.
+    /// - `Err(StoreError)` if there is an error listing the entries.
+    fn scan(
+        &self,
+        options: ScanOptions,
+    ) -> Pin<Box<dyn Future<Output = Result<ScanPage, StoreError>> + Send + '_>>;
+
+    /// Counts the keys starting with `prefix` without reading their values.
+    ///
+    /// # Arguments
+    /// - `prefix`: The key prefix to match. Pass `""` to count every key.
+    ///
+    /// # Returns
+    /// - `Ok(u64)` with the number of unexpired matching keys.
+    /// - `Err(StoreError)` if there is an error counting the keys.
+    fn count(
+        &self,
+        prefix: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>;
+
+    /// Lists the keys starting with `prefix` without reading their values.
+    ///
+    /// # Arguments
+    /// - `prefix`: The key prefix to match. Pass `""` to list every key.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<String>)` with the unexpired matching keys, in key order.
+    /// - `Err(StoreError)` if there is an error listing the keys.
+    fn keys(
+        &self,
+        prefix: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Vec<String>, StoreError>> + Send + '_>,
+    >;
+
     /// Sets a value for a given key in the store, with an optional time-to-live (TTL).
     ///
     /// # Arguments
@@ -76,8 +420,11 @@ pub trait Store: Send + Sync {
     /// - `ttl`: An optional u64 representing the time-to-live in seconds.
     ///
     /// # Returns
-    /// - `Ok(())` if the value is successfully set.
+    /// - `Ok(Some(StoreModel))` with the previous entry if the key already existed.
+    /// - `Ok(None)` if the key did not exist or had expired.
     /// - `Err(StoreError)` if there is an error setting the value.
+    ///
+    /// Reading the previous entry and writing the new value happen atomically.
     fn set(
         &self,
         key: &str,
@@ -91,6 +438,224 @@ pub trait Store: Send + Sync {
         >,
     >;
 
+    /// Sets a value for a given key in the store, with an optional time-to-live
+    /// (TTL), and returns the entry as it was stored.
+    ///
+    /// # Arguments
+    /// - `key`: The key under which the value is stored.
+    /// - `value`: The value to set, represented as a `serde_json::Value`.
+    /// - `ttl`: An optional u64 representing the time-to-live in seconds.
+    ///
+    /// # Returns
+    /// - `Ok(StoreModel)` with the stored entry and its metadata.
+    /// - `Err(StoreError)` if there is an error setting the value.
+    fn set_returning(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<StoreModel, StoreError>> + Send + '_>>;
+
+    /// Sets several values in one atomic operation.
+    ///
+    /// # Arguments
+    /// - `entries`: The `(key, value, ttl)` triples to store, with the TTL in seconds.
+    ///
+    /// # Returns
+    /// - `Ok(())` if every value is successfully set.
+    /// - `Err(StoreError)` if any value could not be set, in which case none of
+    ///   them are.
+    fn set_many(
+        &self,
+        entries: Vec<(String, Value, Option<u64>)>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>>;
+
+    /// Retrieves a value along with its version.
+    ///
+    /// Every write to a key increments its version, starting at 1 when the key
+    /// is first stored. Versions never repeat: a key that is removed, cleared
+    /// or purged and then stored again starts above every version the store
+    /// has removed. Pass the version to `set_if_version` to update the key
+    /// only if nobody else wrote it in the meantime.
+    ///
+    /// # Arguments
+    /// - `key`: A string slice that holds the key for the value to be retrieved.
+    ///
+    /// # Returns
+    /// - `Ok(Some((Value, u64)))` with the value and its version if the key exists.
+    /// - `Ok(None)` if the key does not exist or has expired.
+    /// - `Err(StoreError)` if there is an error retrieving the value.
+    #[allow(clippy::type_complexity)]
+    fn get_with_version(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Option<(Value, u64)>, StoreError>>
+                + Send
+                + '_,
+        >,
+    >;
+
+    /// Sets a value only if the key's current version matches `expected_version`.
+    ///
+    /// An `expected_version` of 0 means the key must be absent (or expired).
+    ///
+    /// # Arguments
+    /// - `key`: The key under which the value is stored.
+    /// - `value`: The value to set, represented as a `serde_json::Value`.
+    /// - `ttl`: An optional u64 representing the time-to-live in seconds.
+    /// - `expected_version`: The version the key must currently have.
+    ///
+    /// # Returns
+    /// - `Ok(u64)` with the new version if the value was written.
+    /// - `Err(StoreError::VersionConflict)` if the key's version has moved on.
+    /// - `Err(StoreError)` if there is an error setting the value.
+    fn set_if_version(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+        expected_version: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>;
+
+    /// Sets a value only if the key does not exist or has expired (`NX`).
+    ///
+    /// # Arguments
+    /// - `key`: The key under which the value is stored.
+    /// - `value`: The value to set, represented as a `serde_json::Value`.
+    /// - `ttl`: An optional u64 representing the time-to-live in seconds.
+    ///
+    /// # Returns
+    /// - `Ok(true)` if the value was written.
+    /// - `Ok(false)` if the key already exists and was left untouched.
+    /// - `Err(StoreError)` if there is an error setting the value.
+    fn set_if_absent(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>;
+
+    /// Sets a value only if the key already exists and has not expired (`XX`).
+    ///
+    /// # Arguments
+    /// - `key`: The key under which the value is stored.
+    /// - `value`: The value to set, represented as a `serde_json::Value`.
+    /// - `ttl`: An optional u64 representing the time-to-live in seconds.
+    ///
+    /// # Returns
+    /// - `Ok(true)` if the value was written.
+    /// - `Ok(false)` if the key does not exist and nothing was written.
+    /// - `Err(StoreError)` if there is an error setting the value.
+    fn set_if_present(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>;
+
+    /// Atomically adds `delta` to the integer stored under a key.
+    ///
+    /// A missing or expired key is created holding `delta`, without a TTL. An
+    /// existing key keeps its TTL.
+    ///
+    /// # Arguments
+    /// - `key`: The key holding the counter.
+    /// - `delta`: The amount to add, which may be negative.
+    ///
+    /// # Returns
+    /// - `Ok(i64)` with the value after the increment.
+    /// - `Err(StoreError::NotNumeric)` if the existing value is not an integer.
+    /// - `Err(StoreError::Overflow)` if the result does not fit in an `i64`.
+    /// - `Err(StoreError)` if there is an error updating the value.
+    fn incr_by(
+        &self,
+        key: &str,
+        delta: i64,
+    ) -> Pin<Box<dyn Future<Output = Result<i64, StoreError>> + Send + '_>>;
+
+    /// Atomically adds `delta` to the number stored under a key.
+    ///
+    /// A missing or expired key is created holding `delta`, without a TTL. An
+    /// existing key keeps its TTL.
+    ///
+    /// # Arguments
+    /// - `key`: The key holding the number.
+    /// - `delta`: The amount to add, which may be negative.
+    ///
+    /// # Returns
+    /// - `Ok(f64)` with the value after the increment.
+    /// - `Err(StoreError::NotNumeric)` if the existing value is not a number.
+    /// - `Err(StoreError::Overflow)` if `delta` or the result is not finite.
+    /// - `Err(StoreError)` if there is an error updating the value.
+    fn incr_by_float(
+        &self,
+        key: &str,
+        delta: f64,
+    ) -> Pin<Box<dyn Future<Output = Result<f64, StoreError>> + Send + '_>>;
+
+    /// Returns the remaining lifetime of a key.
+    ///
+    /// # Arguments
+    /// - `key`: A string slice that holds the key to inspect.
+    ///
+    /// # Returns
+    /// - `Ok(Some(Ttl))` if the key exists.
+    /// - `Ok(None)` if the key does not exist or has expired.
+    /// - `Err(StoreError)` if there is an error reading the expiry.
+    fn ttl(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Option<Ttl>, StoreError>> + Send + '_>,
+    >;
+
+    /// Sets a time-to-live on an existing key, replacing any previous one.
+    ///
+    /// # Arguments
+    /// - `key`: A string slice that holds the key to update.
+    /// - `ttl`: The new time-to-live in seconds, counted from now.
+    ///
+    /// # Returns
+    /// - `Ok(true)` if the key exists and its expiry was updated.
+    /// - `Ok(false)` if the key does not exist or has expired.
+    /// - `Err(StoreError)` if there is an error updating the expiry.
+    fn expire(
+        &self,
+        key: &str,
+        ttl: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>;
+
+    /// Removes the time-to-live from an existing key, so it never expires.
+    ///
+    /// # Arguments
+    /// - `key`: A string slice that holds the key to update.
+    ///
+    /// # Returns
+    /// - `Ok(true)` if the key exists and no longer expires.
+    /// - `Ok(false)` if the key does not exist or has expired.
+    /// - `Err(StoreError)` if there is an error updating the expiry.
+    fn persist(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>;
+
+    /// Restarts the time-to-live of an existing key from now, using the TTL it
+    /// was last given. Keys without a TTL are left untouched.
+    ///
+    /// # Arguments
+    /// - `key`: A string slice that holds the key to refresh.
+    ///
+    /// # Returns
+    /// - `Ok(true)` if the key exists.
+    /// - `Ok(false)` if the key does not exist or has expired.
+    /// - `Err(StoreError)` if there is an error updating the expiry.
+    fn touch(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, StoreError>> + Send + '_>>;
+
     /// Removes a value associated with a given key from the store.
     ///
     /// # Arguments
@@ -117,6 +682,18 @@ pub trait Store: Send + Sync {
         keys: &[&str],
     ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>>;
 
+    /// Deletes every expired value from the store.
+    ///
+    /// Expired values are never returned by reads, but backends may keep them
+    /// around until they are purged.
+    ///
+    /// # Returns
+    /// - `Ok(u64)` with the number of expired values that were deleted.
+    /// - `Err(StoreError)` if there is an error deleting the values.
+    fn purge_expired(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, StoreError>> + Send + '_>>;
+
     /// Clears all values from the store.
     ///
     /// # Returns
@@ -125,6 +702,101 @@ pub trait Store: Send + Sync {
     fn clear(
         &self,
     ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>>;
+
+    /// Writes any buffered writes to the underlying storage.
+    ///
+    /// The default implementation does nothing; backends that acknowledge
+    /// writes before storing them, such as a write-back `TieredStore`,
+    /// override it.
+    ///
+    /// # Returns
+    /// - `Ok(())` once every buffered write has been stored.
+    /// - `Err(StoreError)` if there is an error storing the writes.
+    fn flush(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// A `Store` that can group several operations into one transaction.
+pub trait TransactionalStore: Store {
+    /// Begins a new transaction.
+    ///
+    /// # Returns
+    /// - `Ok(Box<dyn StoreTransaction>)` with the open transaction.
+    /// - `Err(StoreError)` if the transaction could not be started.
+    #[allow(clippy::type_complexity)]
+    fn begin(
+        &self,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Box<dyn StoreTransaction>, StoreError>>
+                + Send
+                + '_,
+        >,
+    >;
+}
+
+/// An open transaction on a `TransactionalStore`.
+///
+/// Reads see the transaction's own writes, and writes become visible to
+/// others only once the transaction is committed. A transaction that is
+/// dropped without being committed is rolled back.
+pub trait StoreTransaction: Send + Sync {
+    /// Retrieves a value associated with a given key within the transaction.
+    ///
+    /// # Returns
+    /// - `Ok(Some(Value))` if the key exists.
+    /// - `Ok(None)` if the key does not exist or has expired.
+    /// - `Err(StoreError)` if there is an error retrieving the value.
+    fn get(
+        &self,
+        key: &str,
+    ) -> Pin<
+        Box<dyn Future<Output = Result<Option<Value>, StoreError>> + Send + '_>,
+    >;
+
+    /// Sets a value for a given key within the transaction, with an optional
+    /// time-to-live (TTL) in seconds.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the value is successfully set.
+    /// - `Err(StoreError)` if there is an error setting the value.
+    fn set(
+        &self,
+        key: &str,
+        value: Value,
+        ttl: Option<u64>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>>;
+
+    /// Removes a value associated with a given key within the transaction.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the value is successfully removed.
+    /// - `Err(StoreError)` if there is an error removing the value.
+    fn remove(
+        &self,
+        key: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>>;
+
+    /// Commits every change made within the transaction.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the transaction is committed.
+    /// - `Err(StoreError)` if the commit fails or the transaction has already ended.
+    fn commit(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>>;
+
+    /// Discards every change made within the transaction.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the transaction is rolled back.
+    /// - `Err(StoreError)` if the rollback fails or the transaction has already ended.
+    fn rollback(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), StoreError>> + Send + '_>>;
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -147,8 +819,40 @@ pub enum StoreError {
     #[error("Database query error: {0}")]
     QueryError(String),
 
-    #[error("The requested key was not found")]
-    NotFound,
+    #[error("Invalid store configuration: {0}")]
+    Configuration(String),
+
+    #[error("Invalid table name `{0}`")]
+    InvalidTableName(String),
+
+    #[error("Version conflict on key `{key}`: expected version {expected}, found {actual}")]
+    VersionConflict {
+        key: String,
+        expected: u64,
+        actual: u64,
+    },
+
+    #[error("The value of key `{key}` is not {expected}")]
+    NotNumeric { key: String, expected: String },
+
+    #[error("Incrementing key `{0}` would overflow")]
+    Overflow(String),
+
+    #[error("The store does not support {0}")]
+    Unsupported(String),
+
+    #[error("Table `{table}` uses schema version {found}, but this release only supports up to {supported}")]
+    UnsupportedSchemaVersion {
+        table: String,
+        found: u64,
+        supported: u64,
+    },
+
+    #[error("Invalid scan cursor `{0}`")]
+    InvalidCursor(String),
+
+    #[error("The requested key `{0}` was not found")]
+    NotFound(String),
 
     #[error("An unknown error has occurred")]
     Unknown,
@@ -0,0 +1,612 @@
+// Copyright © 2024 Aris Ripandi - All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conformance tests for `Store` implementations.
+//!
+//! Enable the `testing` feature and call `run_store_conformance` from a test
+//! to check that a backend behaves like `KyvalStore`:
+//!
+//! ```rust,no_run
+//! # use kyval::adapter::MemoryStore;
+//! # use kyval::testing::run_store_conformance;
+//! #[tokio::main]
+//! async fn main() {
+//!     run_store_conformance(|| async { MemoryStore::new() }).await;
+//! }
+//! ```
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::{ScanOptions, Store, StoreError, Ttl};
+
+/// Runs every conformance check against stores built by `factory`, and
+/// panics with a description of the first mismatch.
+///
+/// Each check starts from a fresh store, which is initialized before use, so
+/// `factory` must return an empty store every time it is called. Transactions
+/// are only checked if the store is transactional.
+///
+/// Checking expiry takes a little over a second, since TTLs are whole
+/// seconds.
+pub async fn run_store_conformance<F, Fut, S>(factory: F)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = S>,
+    S: Store,
+{
+    check_missing_keys(&fresh(&factory).await).await;
+    check_set_and_get(&fresh(&factory).await).await;
+    check_set_many(&fresh(&factory).await).await;
+    check_ordering(&fresh(&factory).await).await;
+    check_scan(&fresh(&factory).await).await;
+    check_versions(&fresh(&factory).await).await;
+    check_conditional_writes(&fresh(&factory).await).await;
+    check_counters(&fresh(&factory).await).await;
+    check_expiry(&fresh(&factory).await).await;
+    check_remove_and_clear(&fresh(&factory).await).await;
+    check_transactions(&fresh(&factory).await).await;
+}
+
+async fn fresh<F, Fut, S>(factory: &F) -> S
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = S>,
+    S: Store,
+{
+    let store = factory().await;
+    store.initialize().await.expect("initialize failed");
+    store
+}
+
+async fn check_missing_keys(store: &dyn Store) {
+    assert_eq!(store.get("missing").await.unwrap(), None, "get");
+    assert!(
+        matches!(
+            store.get_required("missing").await,
+            Err(StoreError::NotFound(key)) if key == "missing"
+        ),
+        "get_required should fail with NotFound"
+    );
+    assert!(store.get_many(&["missing"]).await.unwrap().is_empty());
+    assert!(store.get_many(&[]).await.unwrap().is_empty());
+    assert!(!store.exists("missing").await.unwrap(), "exists");
+    assert_eq!(store.metadata("missing").await.unwrap(), None, "metadata");
+    assert_eq!(store.get_with_version("missing").await.unwrap(), None);
+    assert_eq!(store.ttl("missing").await.unwrap(), None, "ttl");
+    assert!(!store.expire("missing", 60).await.unwrap(), "expire");
+    assert!(!store.persist("missing").await.unwrap(), "persist");
+    assert!(!store.touch("missing").await.unwrap(), "touch");
+    assert!(
+        !store
+            .set_if_present("missing", json!(1), None)
+            .await
+            .unwrap(),
+        "set_if_present"
+    );
+    store
+        .remove("missing")
+        .await
+        .expect("remove of a missing key");
+    store
+        .remove_many(&["missing"])
+        .await
+        .expect("remove_many of a missing key");
+    assert!(store.list().await.unwrap().is_empty(), "list");
+    assert_eq!(store.count("").await.unwrap(), 0, "count");
+    assert!(store.keys("").await.unwrap().is_empty(), "keys");
+}
+
+async fn check_set_and_get(store: &dyn Store) {
+    let values = [
+        ("null", Value::Null),
+        ("bool", json!(true)),
+        ("integer", json!(42)),
+        ("negative", json!(-7)),
+        ("float", json!(1.5)),
+        ("string", json!("life long")),
+        ("numeric_string", json!("42")),
+        ("empty_string", json!("")),
+        ("array", json!(["hola", 1, null])),
+        ("object", json!({ "name": "kyval", "tags": ["a", "b"] })),
+    ];
+
+    for (key, value) in &values {
+        let previous = store.set(key, value.clone(), None).await.unwrap();
+        assert!(previous.is_none(), "set of a new key `{}`", key);
+    }
+    for (key, value) in &values {
+        assert_eq!(
+            store.get(key).await.unwrap().as_ref(),
+            Some(value),
+            "round-trip of `{}`",
+            key
+        );
+        assert_eq!(&store.get_required(key).await.unwrap(), value);
+    }
+
+    let previous = store.set("integer", json!(43), None).await.unwrap();
+    let previous = previous.expect("set should return the previous entry");
+    assert_eq!(previous.key, "integer");
+    assert_eq!(previous.value, json!(42));
+    assert_eq!(store.get("integer").await.unwrap(), Some(json!(43)));
+
+    let model = store.set_returning("returned", json!("v"), None).await;
+    let model = model.unwrap();
+    assert_eq!(model.key, "returned");
+    assert_eq!(model.value, json!("v"));
+    assert_eq!(model.expires_at, None);
+
+    let model = store.set_returning("returned", json!("w"), Some(60)).await;
+    assert!(
+        model.unwrap().expires_at.is_some(),
+        "set_returning with a ttl"
+    );
+
+    let many = store
+        .get_many(&["bool", "string", "missing"])
+        .await
+        .unwrap();
+    let expected = HashMap::from([
+        ("bool".to_string(), json!(true)),
+        ("string".to_string(), json!("life long")),
+    ]);
+    assert_eq!(many, expected, "get_many");
+
+    assert!(
+        store.exists("null").await.unwrap(),
+        "exists of a null value"
+    );
+
+    let meta = store.metadata("string").await.unwrap().unwrap();
+    assert_eq!(meta.key, "string");
+    assert_eq!(meta.version, 1);
+    assert_eq!(meta.expires_at, None);
+    assert!(meta.size > 0, "metadata size");
+}
+
+async fn check_set_many(store: &dyn Store) {
+    store
+        .set_many(Vec::new())
+        .await
+        .expect("set_many of nothing");
+
+    store.set("b", json!("old"), None).await.unwrap();
+    store
+        .set_many(vec![
+            ("a".to_string(), json!(1), None),
+            ("b".to_string(), json!(2), None),
+            ("c".to_string(), json!(3), Some(60)),
+        ])
+        .await
+        .unwrap();
+
+    let many = store.get_many(&["a", "b", "c"]).await.unwrap();
+    assert_eq!(many.len(), 3, "set_many");
+    assert_eq!(many["b"], json!(2), "set_many overwrites");
+    assert!(matches!(
+        store.ttl("c").await.unwrap(),
+        Some(Ttl::Expires(_))
+    ));
+}
+
+async fn check_ordering(store: &dyn Store) {
+    for key in ["b", "a:2", "a", "a:1", "a%", "a_", "c"] {
+        store.set(key, json!(key), None).await.unwrap();
+    }
+
+    let listed: Vec<String> = store
+        .list()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|m| m.key)
+        .collect();
+    assert_eq!(listed, ["a", "a%", "a:1", "a:2", "a_", "b", "c"], "list");
+
+    assert_eq!(store.keys("a:").await.unwrap(), ["a:1", "a:2"], "keys");
+    assert_eq!(
+        store.keys("a%").await.unwrap(),
+        ["a%"],
+        "keys treats the prefix literally"
+    );
+    assert_eq!(store.keys("").await.unwrap(), listed, "keys of everything");
+    assert_eq!(store.count("a").await.unwrap(), 5, "count");
+    assert_eq!(store.count("").await.unwrap(), 7, "count of everything");
+    assert_eq!(store.count("z").await.unwrap(), 0, "count of nothing");
+}
+
+async fn check_scan(store: &dyn Store) {
+    let entries = (0..25)
+        .map(|i| (format!("user:{:02}", i), json!(i), None))
+        .chain([
+            ("user".to_string(), json!("before"), None),
+            ("usez".to_string(), json!("after"), None),
+        ])
+        .collect();
+    store.set_many(entries).await.unwrap();
+
+    for reverse in [false, true] {
+        let mut options = ScanOptions::new()
+            .prefix("user:")
+            .limit(10)
+            .reverse(reverse);
+        let mut keys = Vec::new();
+        let mut pages = 0;
+
+        loop {
+            let page = store.scan(options.clone()).await.unwrap();
+            assert!(page.items.len() <= 10, "scan page size");
+            keys.extend(page.items.into_iter().map(|m| m.key));
+            pages += 1;
+
+            match page.cursor {
+                Some(cursor) => options = options.cursor(cursor),
+                None => break,
+            }
+        }
+
+        let mut expected: Vec<String> =
+            (0..25).map(|i| format!("user:{:02}", i)).collect();
+        if reverse {
+            expected.reverse();
+        }
+        assert_eq!(keys, expected, "scan with reverse = {}", reverse);
+        assert_eq!(pages, 3, "scan page count");
+    }
+
+    let page = store
+        .scan(ScanOptions::new().range("user:05", "user:08"))
+        .await
+        .unwrap();
+    let keys: Vec<String> = page.items.into_iter().map(|m| m.key).collect();
+    assert_eq!(keys, ["user:05", "user:06", "user:07"], "scan range");
+    assert_eq!(page.cursor, None);
+
+    let page = store
+        .scan(ScanOptions::new().range("user:08", "user:05"))
+        .await
+        .unwrap();
+    assert!(page.items.is_empty(), "scan of an empty range");
+
+    let page = store.scan(ScanOptions::new().limit(0)).await.unwrap();
+    assert_eq!(page.items.len(), 1, "scan limit is at least 1");
+
+    assert!(
+        matches!(
+            store.scan(ScanOptions::new().cursor("not a cursor")).await,
+            Err(StoreError::InvalidCursor(_))
+        ),
+        "scan should reject a foreign cursor"
+    );
+}
+
+async fn check_versions(store: &dyn Store) {
+    store.set("key", json!("a"), None).await.unwrap();
+    assert_eq!(
+        store.get_with_version("key").await.unwrap(),
+        Some((json!("a"), 1))
+    );
+
+    store.set("key", json!("b"), None).await.unwrap();
+    assert_eq!(
+        store.get_with_version("key").await.unwrap(),
+        Some((json!("b"), 2)),
+        "set bumps the version"
+    );
+
+    let version = store
+        .set_if_version("key", json!("c"), None, 2)
+        .await
+        .unwrap();
+    assert_eq!(version, 3, "set_if_version");
+
+    assert!(
+        matches!(
+            store.set_if_version("key", json!("d"), None, 2).await,
+            Err(StoreError::VersionConflict {
+                expected: 2,
+                actual: 3,
+                ..
+            })
+        ),
+        "set_if_version with a stale version"
+    );
+    assert_eq!(store.get("key").await.unwrap(), Some(json!("c")));
+
+    assert!(
+        matches!(
+            store.set_if_version("key", json!("d"), None, 0).await,
+            Err(StoreError::VersionConflict {
+                expected: 0,
+                actual: 3,
+                ..
+            })
+        ),
+        "set_if_version expecting an absent key"
+    );
+
+    let version = store
+        .set_if_version("new", json!(1), None, 0)
+        .await
+        .unwrap();
+    assert_eq!(version, 1, "set_if_version of an absent key");
+
+    assert!(
+        matches!(
+            store.set_if_version("missing", json!(1), None, 1).await,
+            Err(StoreError::VersionConflict {
+                expected: 1,
+                actual: 0,
+                ..
+            })
+        ),
+        "set_if_version of a missing key"
+    );
+
+    store.remove("key").await.unwrap();
+    let version = store
+        .set_if_version("key", json!("e"), None, 0)
+        .await
+        .unwrap();
+    assert!(version > 3, "a recreated key reused version {}", version);
+    assert!(
+        matches!(
+            store.set_if_version("key", json!("f"), None, 1).await,
+            Err(StoreError::VersionConflict { expected: 1, .. })
+        ),
+        "set_if_version with a version from before the key was removed"
+    );
+
+    store.clear().await.unwrap();
+    store.set("key", json!("g"), None).await.unwrap();
+    let (_, recreated) = store.get_with_version("key").await.unwrap().unwrap();
+    assert!(
+        recreated > version,
+        "a key recreated after clear reused version {}",
+        recreated
+    );
+}
+
+async fn check_conditional_writes(store: &dyn Store) {
+    assert!(store.set_if_absent("key", json!(1), None).await.unwrap());
+    assert!(!store.set_if_absent("key", json!(2), None).await.unwrap());
+    assert_eq!(store.get("key").await.unwrap(), Some(json!(1)));
+
+    assert!(store
+        .set_if_present("key", json!(3), Some(60))
+        .await
+        .unwrap());
+    assert_eq!(store.get("key").await.unwrap(), Some(json!(3)));
+    assert!(matches!(
+        store.ttl("key").await.unwrap(),
+        Some(Ttl::Expires(_))
+    ));
+}
+
+async fn check_counters(store: &dyn Store) {
+    assert_eq!(store.incr_by("count", 1).await.unwrap(), 1, "incr_by");
+    assert_eq!(store.incr_by("count", 10).await.unwrap(), 11);
+    assert_eq!(store.incr_by("count", -12).await.unwrap(), -1);
+    assert_eq!(store.get("count").await.unwrap(), Some(json!(-1)));
+
+    store.set("max", json!(i64::MAX), None).await.unwrap();
+    assert!(
+        matches!(
+            store.incr_by("max", 1).await,
+            Err(StoreError::Overflow(key)) if key == "max"
+        ),
+        "incr_by past i64::MAX"
+    );
+    assert_eq!(store.get("max").await.unwrap(), Some(json!(i64::MAX)));
+
+    for value in [json!("text"), json!("1"), json!(1.5), json!([1])] {
+        store.set("other", value.clone(), None).await.unwrap();
+        assert!(
+            matches!(
+                store.incr_by("other", 1).await,
+                Err(StoreError::NotNumeric { .. })
+            ),
+            "incr_by of {}",
+            value
+        );
+        assert_eq!(store.get("other").await.unwrap(), Some(value));
+    }
+
+    assert_eq!(store.incr_by_float("float", 1.5).await.unwrap(), 1.5);
+    assert_eq!(store.incr_by_float("float", 0.25).await.unwrap(), 1.75);
+    assert_eq!(store.incr_by_float("count", 0.5).await.unwrap(), -0.5);
+
+    // Sums that are not exact in binary must keep every significant digit.
+    assert_eq!(store.incr_by_float("inexact", 0.1).await.unwrap(), 0.1);
+    assert_eq!(
+        store.incr_by_float("inexact", 0.2).await.unwrap(),
+        0.1 + 0.2
+    );
+    store
+        .set("precise", json!(123_456_789.123_456_79), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        store.incr_by_float("precise", 1.0).await.unwrap(),
+        123_456_789.123_456_79 + 1.0
+    );
+    assert_eq!(
+        store.get("precise").await.unwrap(),
+        Some(json!(123_456_789.123_456_79 + 1.0)),
+        "incr_by_float stores the exact sum"
+    );
+    assert!(
+        matches!(
+            store.incr_by("count", 1).await,
+            Err(StoreError::NotNumeric { .. })
+        ),
+        "incr_by of a float"
+    );
+
+    store.set("other", json!("text"), None).await.unwrap();
+    assert!(matches!(
+        store.incr_by_float("other", 1.0).await,
+        Err(StoreError::NotNumeric { .. })
+    ));
+    assert!(matches!(
+        store.incr_by_float("float", f64::NAN).await,
+        Err(StoreError::Overflow(_))
+    ));
+
+    store.set("ttl", json!(1), Some(60)).await.unwrap();
+    store.incr_by("ttl", 1).await.unwrap();
+    assert!(
+        matches!(store.ttl("ttl").await.unwrap(), Some(Ttl::Expires(_))),
+        "incr_by keeps the ttl"
+    );
+}
+
+async fn check_expiry(store: &dyn Store) {
+    store.set("persistent", json!(1), None).await.unwrap();
+    store.set("short", json!(1), Some(1)).await.unwrap();
+    store.set("absent", json!(1), Some(1)).await.unwrap();
+    store.set("present", json!(1), Some(1)).await.unwrap();
+    store.set("counter", json!(5), Some(1)).await.unwrap();
+    store.set("kept", json!(1), Some(1)).await.unwrap();
+    store.set("long", json!(1), Some(3600)).await.unwrap();
+
+    assert_eq!(
+        store.ttl("persistent").await.unwrap(),
+        Some(Ttl::Persistent)
+    );
+    match store.ttl("long").await.unwrap() {
+        Some(Ttl::Expires(remaining)) => assert!(
+            remaining <= Duration::from_secs(3600)
+                && remaining > Duration::from_secs(3500),
+            "ttl reports the remaining lifetime, got {:?}",
+            remaining
+        ),
+        other => panic!("ttl of an expiring key, got {:?}", other),
+    }
+    assert!(store.get("short").await.unwrap().is_some(), "before expiry");
+    assert!(store.persist("kept").await.unwrap(), "persist");
+    assert_eq!(store.ttl("kept").await.unwrap(), Some(Ttl::Persistent));
+
+    assert!(store.expire("persistent", 3600).await.unwrap(), "expire");
+    assert!(matches!(
+        store.ttl("persistent").await.unwrap(),
+        Some(Ttl::Expires(_))
+    ));
+    assert!(store.persist("persistent").await.unwrap());
+    assert!(store.touch("long").await.unwrap(), "touch");
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    for key in ["short", "absent", "present", "counter"] {
+        assert_eq!(store.get(key).await.unwrap(), None, "get of `{}`", key);
+    }
+    assert!(!store.exists("short").await.unwrap(), "exists after expiry");
+    assert_eq!(store.metadata("short").await.unwrap(), None);
+    assert_eq!(store.get_with_version("short").await.unwrap(), None);
+    assert_eq!(store.ttl("short").await.unwrap(), None);
+    assert!(store.get_many(&["short"]).await.unwrap().is_empty());
+    assert!(!store.expire("short", 60).await.unwrap());
+    assert!(!store.persist("short").await.unwrap());
+    assert!(!store.touch("short").await.unwrap());
+
+    let keys = store.keys("").await.unwrap();
+    assert_eq!(keys, ["kept", "long", "persistent"], "keys after expiry");
+    assert_eq!(store.count("").await.unwrap(), 3, "count after expiry");
+    assert_eq!(store.list().await.unwrap().len(), 3, "list after expiry");
+    assert_eq!(store.scan(ScanOptions::new()).await.unwrap().items.len(), 3);
+
+    assert!(
+        store.set_if_absent("absent", json!(2), None).await.unwrap(),
+        "set_if_absent over an expired key"
+    );
+    assert!(
+        !store
+            .set_if_present("present", json!(2), None)
+            .await
+            .unwrap(),
+        "set_if_present over an expired key"
+    );
+    assert_eq!(
+        store.incr_by("counter", 1).await.unwrap(),
+        1,
+        "incr_by restarts an expired counter"
+    );
+    assert_eq!(store.ttl("counter").await.unwrap(), Some(Ttl::Persistent));
+    assert!(
+        store.set("short", json!(2), None).await.unwrap().is_none(),
+        "set over an expired key returns no previous entry"
+    );
+
+    assert_eq!(
+        store.purge_expired().await.unwrap(),
+        1,
+        "purge_expired removes the expired `present` key"
+    );
+    assert_eq!(store.purge_expired().await.unwrap(), 0);
+}
+
+async fn check_remove_and_clear(store: &dyn Store) {
+    for key in ["a", "b", "c", "d"] {
+        store.set(key, json!(key), None).await.unwrap();
+    }
+
+    store.remove("a").await.unwrap();
+    assert_eq!(store.get("a").await.unwrap(), None, "remove");
+
+    store
+        .remove_many(&[])
+        .await
+        .expect("remove_many of nothing");
+    assert_eq!(store.count("").await.unwrap(), 3);
+
+    store.remove_many(&["b", "c", "missing"]).await.unwrap();
+    assert_eq!(store.keys("").await.unwrap(), ["d"], "remove_many");
+
+    store.clear().await.unwrap();
+    assert_eq!(store.count("").await.unwrap(), 0, "clear");
+    store.clear().await.expect("clear of an empty store");
+
+    store.set("a", json!(1), None).await.unwrap();
+    assert_eq!(store.get("a").await.unwrap(), Some(json!(1)), "after clear");
+}
+
+async fn check_transactions(store: &dyn Store) {
+    let Some(transactional) = store.as_transactional() else {
+        return;
+    };
+
+    store.set("balance", json!(10), None).await.unwrap();
+
+    let tx = transactional.begin().await.unwrap();
+    assert_eq!(tx.get("balance").await.unwrap(), Some(json!(10)));
+    tx.set("balance", json!(5), None).await.unwrap();
+    tx.set("audit", json!("debit"), None).await.unwrap();
+    assert_eq!(tx.get("balance").await.unwrap(), Some(json!(5)));
+    tx.rollback().await.unwrap();
+    drop(tx);
+
+    assert_eq!(store.get("balance").await.unwrap(), Some(json!(10)));
+    assert_eq!(store.get("audit").await.unwrap(), None, "rollback");
+
+    let tx = transactional.begin().await.unwrap();
+    tx.set("balance", json!(5), None).await.unwrap();
+    tx.remove("missing").await.unwrap();
+    tx.set("audit", json!("debit"), Some(60)).await.unwrap();
+    tx.commit().await.unwrap();
+    drop(tx);
+
+    assert_eq!(store.get("balance").await.unwrap(), Some(json!(5)));
+    assert_eq!(store.get("audit").await.unwrap(), Some(json!("debit")));
+    assert!(matches!(
+        store.ttl("audit").await.unwrap(),
+        Some(Ttl::Expires(_))
+    ));
+}
@@ -0,0 +1,47 @@
+// Copyright © 2024 Aris Ripandi - All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use kyval::adapter::MemoryStore;
+use kyval::testing::run_store_conformance;
+
+#[cfg(feature = "libsql")]
+#[tokio::test]
+async fn kyval_store_conforms() {
+    use kyval::adapter::KyvalStoreBuilder;
+
+    run_store_conformance(|| async {
+        KyvalStoreBuilder::new()
+            .uri(":memory:")
+            .build()
+            .await
+            .unwrap()
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn memory_store_conforms() {
+    run_store_conformance(|| async { MemoryStore::new() }).await;
+}
+
+#[tokio::test]
+async fn tiered_store_conforms() {
+    use kyval::adapter::{TieredStore, WritePolicy};
+
+    run_store_conformance(|| async {
+        TieredStore::new(MemoryStore::new(), MemoryStore::new()).capacity(4)
+    })
+    .await;
+
+    run_store_conformance(|| async {
+        TieredStore::new(MemoryStore::new(), MemoryStore::new())
+            .capacity(4)
+            .write_policy(WritePolicy::WriteBack)
+    })
+    .await;
+}
@@ -0,0 +1,191 @@
+// Copyright © 2024 Aris Ripandi - All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use kyval::adapter::MemoryStore;
+use kyval::{Kyval, KyvalError, StoreError, Ttl};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn kyval() -> Kyval {
+    Kyval::try_new(MemoryStore::new()).await.unwrap()
+}
+
+#[tokio::test]
+async fn set_if_version_keeps_the_given_ttl() {
+    let kyval = kyval().await;
+
+    kyval.set_with_ttl("key", 1, 100).await.unwrap();
+    let (_, version) = kyval.get_with_version("key").await.unwrap().unwrap();
+
+    kyval
+        .set_if_version("key", 2, Some(100), version)
+        .await
+        .unwrap();
+    assert!(matches!(
+        kyval.ttl("key").await.unwrap(),
+        Some(Ttl::Expires(_))
+    ));
+
+    kyval
+        .set_if_version("key", 3, None, version + 1)
+        .await
+        .unwrap();
+    assert_eq!(kyval.ttl("key").await.unwrap(), Some(Ttl::Persistent));
+}
+
+#[tokio::test]
+async fn get_as_reports_the_key_that_failed_to_decode() {
+    let kyval = kyval().await;
+
+    kyval.set("number", "not a number").await.unwrap();
+
+    match kyval.get_as::<i32>("number").await {
+        Err(KyvalError::DecodeError { key, .. }) => assert_eq!(key, "number"),
+        other => panic!("expected a decode error, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "libsql")]
+#[tokio::test]
+async fn transaction_commits_on_ok_and_rolls_back_on_err() {
+    let kyval = Kyval::in_memory().await.unwrap();
+    kyval.set("from", 100).await.unwrap();
+
+    kyval
+        .transaction(|tx| {
+            Box::pin(async move {
+                let value = tx.get("from").await?.unwrap();
+                tx.set("to", value).await?;
+                tx.remove("from").await?;
+                Ok(())
+            })
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(kyval.get_as::<i32>("from").await.unwrap(), None);
+    assert_eq!(kyval.get_as::<i32>("to").await.unwrap(), Some(100));
+
+    let result: Result<(), KyvalError> = kyval
+        .transaction(|tx| {
+            Box::pin(async move {
+                tx.set("to", 200).await?;
+                tx.set("other", 1).await?;
+                Err(StoreError::Unsupported("rollback".to_string()).into())
+            })
+        })
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(kyval.get_as::<i32>("to").await.unwrap(), Some(100));
+    assert_eq!(kyval.get_as::<i32>("other").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn transaction_is_unsupported_without_a_transactional_store() {
+    let kyval = kyval().await;
+
+    let result = kyval.transaction(|_| Box::pin(async { Ok(()) })).await;
+
+    assert!(matches!(
+        result,
+        Err(KyvalError::StoreError(StoreError::Unsupported(_)))
+    ));
+}
+
+#[tokio::test]
+async fn stream_walks_every_page_of_a_prefix() {
+    use futures::TryStreamExt;
+
+    let kyval = kyval().await;
+
+    let entries = (0..250).map(|i| (format!("user:{:03}", i), i, None));
+    kyval.set_many(entries).await.unwrap();
+    kyval.set("other", 0).await.unwrap();
+    kyval.set("users", 0).await.unwrap();
+
+    let keys: Vec<String> = kyval
+        .stream("user:")
+        .map_ok(|model| model.key)
+        .try_collect()
+        .await
+        .unwrap();
+
+    let expected: Vec<String> =
+        (0..250).map(|i| format!("user:{:03}", i)).collect();
+    assert_eq!(keys, expected);
+}
+
+#[tokio::test]
+async fn get_or_insert_with_runs_one_loader_for_concurrent_misses() {
+    let kyval = Arc::new(kyval().await);
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let tasks: Vec<_> = (0..20)
+        .map(|_| {
+            let kyval = kyval.clone();
+            let calls = calls.clone();
+            tokio::spawn(async move {
+                kyval
+                    .get_or_insert_with("key", Some(60), || async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Ok(42)
+                    })
+                    .await
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        assert_eq!(task.await.unwrap().unwrap(), 42);
+    }
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(kyval.get_as::<i32>("key").await.unwrap(), Some(42));
+}
+
+#[tokio::test]
+async fn get_or_insert_with_lets_the_next_waiter_load_after_a_failure() {
+    let kyval = Arc::new(kyval().await);
+    let (started, running) = tokio::sync::oneshot::channel();
+    let failed = Arc::new(AtomicBool::new(false));
+
+    let failing = tokio::spawn({
+        let kyval = kyval.clone();
+        let failed = failed.clone();
+        async move {
+            kyval
+                .get_or_insert_with::<i32, _, _>("key", None, || async move {
+                    started.send(()).unwrap();
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    failed.store(true, Ordering::SeqCst);
+                    Err(StoreError::Unsupported("loader".to_string()).into())
+                })
+                .await
+        }
+    });
+
+    // Wait until the first loader holds the key before queueing behind it.
+    running.await.unwrap();
+    let value = kyval
+        .get_or_insert_with("key", None, || async {
+            assert!(
+                failed.load(Ordering::SeqCst),
+                "waited for the first loader"
+            );
+            Ok(7)
+        })
+        .await
+        .unwrap();
+
+    assert!(failing.await.unwrap().is_err());
+    assert_eq!(value, 7);
+    assert_eq!(kyval.get_as::<i32>("key").await.unwrap(), Some(7));
+}
@@ -0,0 +1,250 @@
+// Copyright © 2024 Aris Ripandi - All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(feature = "libsql")]
+
+use kyval::adapter::{KyvalStoreBuilder, MemoryStore, MAX_TABLE_NAME_LEN};
+use kyval::{Kyval, KyvalError, Store, StoreError};
+use libsql::{Builder, Connection};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn connect() -> Arc<Connection> {
+    let db = Builder::new_local(":memory:").build().await.unwrap();
+    Arc::new(db.connect().unwrap())
+}
+
+async fn row_count(conn: &Connection, table_name: &str) -> u64 {
+    let query = format!("SELECT COUNT(*) FROM \"{}\"", table_name);
+    let mut rows = conn.query(&query, ()).await.unwrap();
+    rows.next().await.unwrap().unwrap().get(0).unwrap()
+}
+
+#[tokio::test]
+async fn sweeper_deletes_expired_rows() {
+    let conn = connect().await;
+    let store = KyvalStoreBuilder::new()
+        .connnection(conn.clone())
+        .sweep_interval(Duration::from_millis(100))
+        .build()
+        .await
+        .unwrap();
+    store.initialize().await.unwrap();
+
+    store.set("expiring", json!(1), Some(1)).await.unwrap();
+    store.set("persistent", json!(2), None).await.unwrap();
+    assert_eq!(row_count(&conn, "kv_store").await, 2);
+
+    tokio::time::sleep(Duration::from_millis(1400)).await;
+
+    assert_eq!(row_count(&conn, "kv_store").await, 1);
+    assert_eq!(store.get("persistent").await.unwrap(), Some(json!(2)));
+}
+
+#[tokio::test]
+async fn sweeper_stops_when_the_store_is_dropped() {
+    let conn = connect().await;
+    let store = KyvalStoreBuilder::new()
+        .connnection(conn.clone())
+        .sweep_interval(Duration::from_millis(50))
+        .build()
+        .await
+        .unwrap();
+    store.initialize().await.unwrap();
+
+    // The test, the store and the sweeper task each hold the connection.
+    assert_eq!(Arc::strong_count(&conn), 3);
+
+    drop(store);
+
+    for _ in 0..20 {
+        if Arc::strong_count(&conn) == 1 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the sweeper kept running after the store was dropped");
+}
+
+#[tokio::test]
+async fn build_rejects_invalid_table_names() {
+    let too_long = "a".repeat(MAX_TABLE_NAME_LEN + 1);
+    let names = [
+        "a; DROP TABLE x",
+        "a\"b",
+        "sqlite_master",
+        "kyval_schema",
+        "",
+        "1table",
+        too_long.as_str(),
+    ];
+
+    for name in names {
+        let result = KyvalStoreBuilder::new()
+            .uri(":memory:")
+            .table_name(name)
+            .build()
+            .await;
+
+        assert!(
+            matches!(result, Err(StoreError::InvalidTableName(ref n)) if n == name),
+            "{:?} was accepted",
+            name
+        );
+    }
+}
+
+#[tokio::test]
+async fn unusual_valid_table_names_round_trip() {
+    let conn = connect().await;
+
+    for name in ["Mixed_Case", "_leading_underscore", "tenant__42"] {
+        let store = KyvalStoreBuilder::new()
+            .connnection(conn.clone())
+            .table_name(name)
+            .build()
+            .await
+            .unwrap();
+        store.initialize().await.unwrap();
+
+        store.set("key", json!(name), None).await.unwrap();
+        assert_eq!(store.get("key").await.unwrap(), Some(json!(name)));
+        assert_eq!(row_count(&conn, name).await, 1);
+    }
+}
+
+#[tokio::test]
+async fn build_reports_bad_configuration() {
+    let builders = [
+        KyvalStoreBuilder::new(),
+        KyvalStoreBuilder::new().uri("/nonexistent/kyval/store.db"),
+        KyvalStoreBuilder::new().uri("not a url").token("token"),
+        KyvalStoreBuilder::new()
+            .uri("ftp://example.com")
+            .token("token"),
+    ];
+
+    for builder in builders {
+        let result = builder.build().await;
+        assert!(
+            matches!(result, Err(StoreError::Configuration(_))),
+            "{:?}",
+            result.err()
+        );
+    }
+}
+
+#[tokio::test]
+async fn get_many_crosses_the_parameter_limit() {
+    let store = KyvalStoreBuilder::new()
+        .uri(":memory:")
+        .build()
+        .await
+        .unwrap();
+    store.initialize().await.unwrap();
+    let memory = MemoryStore::new();
+
+    // Every tenth entry expires; keys past 2000 are never written.
+    let entries: Vec<_> = (0..2000)
+        .map(|i| {
+            let ttl = (i % 10 == 0).then_some(1);
+            (format!("key_{}", i), json!(i), ttl)
+        })
+        .collect();
+    store.set_many(entries.clone()).await.unwrap();
+    memory.set_many(entries).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    let keys: Vec<String> = (0..2500).map(|i| format!("key_{}", i)).collect();
+    let keys: Vec<&str> = keys.iter().map(|k| k.as_str()).collect();
+
+    let found = store.get_many(&keys).await.unwrap();
+    assert_eq!(found.len(), 1800);
+    assert_eq!(found, memory.get_many(&keys).await.unwrap());
+}
+
+#[tokio::test]
+async fn set_many_is_all_or_nothing() {
+    let conn = connect().await;
+    let store = KyvalStoreBuilder::new()
+        .connnection(conn.clone())
+        .build()
+        .await
+        .unwrap();
+    store.initialize().await.unwrap();
+    store.set("existing", json!("before"), None).await.unwrap();
+
+    // Fail the batch partway through.
+    conn.execute(
+        "CREATE TRIGGER poison BEFORE INSERT ON kv_store WHEN NEW.key = 'poison' BEGIN SELECT RAISE(ABORT, 'poisoned'); END",
+        (),
+    )
+    .await
+    .unwrap();
+
+    let entries = vec![
+        ("first".to_string(), json!(1), None),
+        ("existing".to_string(), json!("after"), None),
+        ("poison".to_string(), json!(2), None),
+        ("last".to_string(), json!(3), None),
+    ];
+    assert!(store.set_many(entries).await.is_err());
+
+    assert_eq!(store.get("first").await.unwrap(), None);
+    assert_eq!(store.get("last").await.unwrap(), None);
+    assert_eq!(store.get("existing").await.unwrap(), Some(json!("before")));
+    assert_eq!(row_count(&conn, "kv_store").await, 1);
+}
+
+#[tokio::test]
+async fn stores_sharing_a_connection_wait_for_each_others_transactions() {
+    let conn = connect().await;
+    let tenant = |name: &'static str| {
+        let conn = conn.clone();
+        async move {
+            let store = KyvalStoreBuilder::new()
+                .connnection(conn)
+                .table_name(name)
+                .build()
+                .await
+                .unwrap();
+            Kyval::try_new(store).await.unwrap()
+        }
+    };
+    let tenant_a = tenant("tenant_a").await;
+    let tenant_b = Arc::new(tenant("tenant_b").await);
+
+    let (started, running) = tokio::sync::oneshot::channel();
+    let writer = tokio::spawn({
+        let tenant_b = tenant_b.clone();
+        async move {
+            running.await.unwrap();
+            tenant_b.incr("counter").await?;
+            tenant_b.set_many([("many", 1, None)]).await
+        }
+    });
+
+    let result: Result<(), KyvalError> = tenant_a
+        .transaction(|tx| {
+            Box::pin(async move {
+                tx.set("key", 1).await?;
+                started.send(()).unwrap();
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Err(StoreError::Unsupported("rollback".to_string()).into())
+            })
+        })
+        .await;
+    assert!(result.is_err());
+
+    writer.await.unwrap().unwrap();
+    assert_eq!(tenant_a.get("key").await.unwrap(), None);
+    assert_eq!(tenant_b.get_as::<i64>("counter").await.unwrap(), Some(1));
+    assert_eq!(tenant_b.get_as::<i64>("many").await.unwrap(), Some(1));
+}
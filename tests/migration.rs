@@ -0,0 +1,162 @@
+// Copyright © 2024 Aris Ripandi - All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(feature = "libsql")]
+
+use kyval::adapter::{KyvalStore, KyvalStoreBuilder, SCHEMA_VERSION};
+use kyval::{Store, StoreError};
+use libsql::{Builder, Connection};
+use serde_json::json;
+use std::sync::Arc;
+
+/// The table layout written by the first release, before schema versioning.
+const BASELINE_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS "legacy" (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL,
+        updated_at TEXT DEFAULT (datetime('now', 'localtime')),
+        UNIQUE(key)
+    ) STRICT;
+    CREATE INDEX IF NOT EXISTS "legacy_key_idx" ON "legacy" (key);
+    CREATE TRIGGER IF NOT EXISTS "legacy_update_trigger"
+    AFTER UPDATE ON "legacy"
+    BEGIN
+        UPDATE "legacy" SET updated_at = datetime('now', 'localtime') WHERE key = NEW.key;
+    END;
+    INSERT INTO "legacy" (key, value, updated_at) VALUES
+        ('number', '42', '2024-01-01 00:00:00'),
+        ('empty', '', '2024-01-01 00:00:00'),
+        ('object', '{"a":1}', '2024-01-01 00:00:00'),
+        ('text', 'hello', '2024-01-01 00:00:00');
+"#;
+
+/// A table and schema record written by the release before versions were
+/// kept monotonic across removals.
+const VERSION_5_SCHEMA: &str = r#"
+    CREATE TABLE kyval_schema (
+        table_name TEXT PRIMARY KEY,
+        version INTEGER NOT NULL
+    ) STRICT;
+    INSERT INTO kyval_schema (table_name, version) VALUES ('previous', 5);
+    CREATE TABLE "previous" (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL,
+        expires_at INTEGER,
+        ttl INTEGER,
+        version INTEGER NOT NULL DEFAULT 1,
+        created_at INTEGER,
+        updated_at INTEGER,
+        UNIQUE(key)
+    ) STRICT;
+    INSERT INTO "previous" (key, value, version) VALUES ('key', '"a"', 3);
+"#;
+
+async fn store(conn: &Arc<Connection>, table_name: &str) -> KyvalStore {
+    KyvalStoreBuilder::new()
+        .connnection(conn.clone())
+        .table_name(table_name)
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn initialize_migrates_a_baseline_table() {
+    let db = Builder::new_local(":memory:").build().await.unwrap();
+    let conn = Arc::new(db.connect().unwrap());
+    conn.execute_batch(BASELINE_SCHEMA).await.unwrap();
+
+    // Another store whose table name happens to end like a scratch table.
+    let neighbour = store(&conn, "legacy_rebuild").await;
+    neighbour.initialize().await.unwrap();
+    neighbour.set("tenant", json!("kept"), None).await.unwrap();
+
+    let legacy = store(&conn, "legacy").await;
+    legacy.initialize().await.unwrap();
+
+    assert_eq!(legacy.get("number").await.unwrap(), Some(json!(42)));
+    assert_eq!(legacy.get("object").await.unwrap(), Some(json!({"a": 1})));
+    assert_eq!(legacy.get("text").await.unwrap(), Some(json!("hello")));
+    // Older releases wrote `null` as the empty string.
+    assert_eq!(legacy.get("empty").await.unwrap(), Some(json!(null)));
+
+    let meta = legacy.metadata("number").await.unwrap().unwrap();
+    assert_eq!(meta.version, 1);
+    assert!(meta.updated_at.is_some_and(|updated_at| updated_at > 0));
+
+    // The migrated table supports everything added since.
+    legacy.set("number", json!(43), Some(60)).await.unwrap();
+    assert_eq!(legacy.incr_by("number", 1).await.unwrap(), 44);
+    assert_eq!(legacy.count("").await.unwrap(), 4);
+
+    assert_eq!(neighbour.get("tenant").await.unwrap(), Some(json!("kept")));
+
+    // Initializing again finds the table up to date.
+    store(&conn, "legacy").await.initialize().await.unwrap();
+    assert_eq!(legacy.get("text").await.unwrap(), Some(json!("hello")));
+}
+
+#[tokio::test]
+async fn initialize_keeps_versions_monotonic_in_a_version_5_table() {
+    let db = Builder::new_local(":memory:").build().await.unwrap();
+    let conn = Arc::new(db.connect().unwrap());
+    conn.execute_batch(VERSION_5_SCHEMA).await.unwrap();
+
+    let previous = store(&conn, "previous").await;
+    previous.initialize().await.unwrap();
+    assert_eq!(
+        previous.get_with_version("key").await.unwrap(),
+        Some((json!("a"), 3))
+    );
+
+    previous.remove("key").await.unwrap();
+    assert_eq!(
+        previous
+            .set_if_version("key", json!("b"), None, 0)
+            .await
+            .unwrap(),
+        4
+    );
+}
+
+#[tokio::test]
+async fn initialize_refuses_a_newer_schema() {
+    let db = Builder::new_local(":memory:").build().await.unwrap();
+    let conn = Arc::new(db.connect().unwrap());
+
+    let current = store(&conn, "future").await;
+    current.initialize().await.unwrap();
+    current.set("key", json!("kept"), None).await.unwrap();
+
+    let found = SCHEMA_VERSION + 1;
+    conn.execute(
+        "UPDATE kyval_schema SET version = ?1 WHERE table_name = 'future'",
+        libsql::params![found as i64],
+    )
+    .await
+    .unwrap();
+
+    let newer = store(&conn, "future").await;
+    assert!(matches!(
+        newer.initialize().await,
+        Err(StoreError::UnsupportedSchemaVersion { table, found: f, supported })
+            if table == "future" && f == found && supported == SCHEMA_VERSION
+    ));
+
+    // The table and its schema record are left as they were.
+    assert_eq!(current.get("key").await.unwrap(), Some(json!("kept")));
+    let mut rows = conn
+        .query(
+            "SELECT version FROM kyval_schema WHERE table_name = 'future'",
+            (),
+        )
+        .await
+        .unwrap();
+    let row = rows.next().await.unwrap().unwrap();
+    assert_eq!(row.get::<u64>(0).unwrap(), found);
+}
@@ -0,0 +1,160 @@
+// Copyright © 2024 Aris Ripandi - All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(feature = "libsql")]
+
+use kyval::adapter::{
+    KyvalStore, KyvalStoreBuilder, MemoryStore, TieredStore, WritePolicy,
+};
+use kyval::{Kyval, Store, TransactionalStore};
+use libsql::{Builder, Connection};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+async fn connect() -> Arc<Connection> {
+    let db = Builder::new_local(":memory:").build().await.unwrap();
+    Arc::new(db.connect().unwrap())
+}
+
+async fn store(conn: &Arc<Connection>) -> KyvalStore {
+    KyvalStoreBuilder::new()
+        .connnection(conn.clone())
+        .build()
+        .await
+        .unwrap()
+}
+
+async fn write_back(
+    conn: &Arc<Connection>,
+    capacity: usize,
+) -> TieredStore<MemoryStore, KyvalStore> {
+    let store = TieredStore::new(MemoryStore::new(), store(conn).await)
+        .capacity(capacity)
+        .write_policy(WritePolicy::WriteBack);
+    store.initialize().await.unwrap();
+    store
+}
+
+#[tokio::test]
+async fn kyval_flush_writes_buffered_writes_through() {
+    let conn = connect().await;
+    let backing = store(&conn).await;
+    let kyval = Kyval::try_new(write_back(&conn, 10).await).await.unwrap();
+
+    kyval.set("key", "value").await.unwrap();
+    assert_eq!(backing.get("key").await.unwrap(), None);
+
+    kyval.flush().await.unwrap();
+    assert_eq!(backing.get("key").await.unwrap(), Some(json!("value")));
+}
+
+#[tokio::test]
+async fn a_failed_write_back_is_not_applied_later() {
+    let conn = connect().await;
+    let backing = store(&conn).await;
+    let tiered = write_back(&conn, 1).await;
+
+    tiered.set("buffered", json!(1), None).await.unwrap();
+
+    // Make the next flush fail.
+    conn.execute("DROP TABLE kv_store", ()).await.unwrap();
+
+    assert!(tiered.set("rejected", json!(2), None).await.is_err());
+    let entries = vec![
+        ("many_a".to_string(), json!(3), None),
+        ("many_b".to_string(), json!(4), None),
+    ];
+    assert!(tiered.set_many(entries).await.is_err());
+
+    backing.initialize().await.unwrap();
+
+    assert_eq!(tiered.get("rejected").await.unwrap(), None);
+    assert_eq!(tiered.get("many_a").await.unwrap(), None);
+    assert_eq!(tiered.get("buffered").await.unwrap(), Some(json!(1)));
+
+    tiered.flush().await.unwrap();
+
+    assert_eq!(backing.keys("").await.unwrap(), vec!["buffered"]);
+}
+
+#[tokio::test]
+async fn buffered_writes_stay_visible_while_they_are_flushed() {
+    let conn = connect().await;
+    let backing = store(&conn).await;
+    let tiered = Arc::new(write_back(&conn, 1).await);
+
+    backing.set("cached", json!(0), None).await.unwrap();
+    tiered.set("buffered", json!(1), None).await.unwrap();
+    // Push the buffered write out of the cache, so only the buffer has it.
+    tiered.get("cached").await.unwrap();
+
+    // Hold the connection, so the flush stalls writing to the backing store.
+    let tx = backing.begin().await.unwrap();
+    let flush = tokio::spawn({
+        let tiered = tiered.clone();
+        async move { tiered.flush().await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!flush.is_finished());
+
+    let found = tokio::time::timeout(
+        Duration::from_secs(1),
+        tiered.get_many(&["buffered"]),
+    )
+    .await
+    .expect("get_many waited for the flush")
+    .unwrap();
+    assert_eq!(found.get("buffered"), Some(&json!(1)));
+    let exists =
+        tokio::time::timeout(Duration::from_secs(1), tiered.exists("buffered"))
+            .await
+            .expect("exists waited for the flush")
+            .unwrap();
+    assert!(exists);
+
+    tx.rollback().await.unwrap();
+    flush.await.unwrap().unwrap();
+    assert_eq!(backing.get("buffered").await.unwrap(), Some(json!(1)));
+    assert_eq!(tiered.get_many(&["buffered"]).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn write_back_set_returns_the_backing_expiry() {
+    let conn = connect().await;
+    let backing = store(&conn).await;
+    let tiered = TieredStore::new(MemoryStore::new(), store(&conn).await)
+        .cache_ttl(1)
+        .write_policy(WritePolicy::WriteBack);
+    tiered.initialize().await.unwrap();
+
+    backing.set("key", json!(1), Some(60)).await.unwrap();
+    // Cache the entry, with its expiry shortened to the cache TTL.
+    tiered.get("key").await.unwrap();
+
+    let before = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let previous = tiered.set("key", json!(2), None).await.unwrap().unwrap();
+    assert_eq!(previous.value, json!(1));
+    assert!(previous
+        .expires_at
+        .is_some_and(|expires_at| expires_at > before + 50_000));
+
+    let previous = tiered.set("key", json!(3), None).await.unwrap().unwrap();
+    assert_eq!(previous.value, json!(2));
+    assert_eq!(previous.expires_at, None);
+
+    tiered.flush().await.unwrap();
+    assert!(tiered
+        .set("missing", json!(4), None)
+        .await
+        .unwrap()
+        .is_none());
+}